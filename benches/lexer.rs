@@ -0,0 +1,37 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use crusty_json::{lexer, lexer_borrowed, LexerOptions};
+
+/// Builds an array of `count` small records, a few megabytes for the default count
+fn sample_document(count: usize) -> String {
+    let mut json = String::from("[");
+
+    for i in 0..count {
+        if i > 0 {
+            json.push(',');
+        }
+        json.push_str(&format!(
+            r#"{{"id": {}, "name": "fulano {}", "bio": "Mañana \"quizás\" ☕", "score": {}.5e-3, "active": true, "tags": ["a", "b", null]}}"#,
+            i,
+            i,
+            i % 1000
+        ));
+    }
+
+    json.push(']');
+    json
+}
+
+fn bench_lexer(c: &mut Criterion) {
+    let input = sample_document(30_000);
+
+    let mut group = c.benchmark_group("lexer");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.bench_function("records", |b| b.iter(|| lexer(&input).unwrap()));
+    group.bench_function("records_borrowed", |b| {
+        b.iter(|| lexer_borrowed(&input, &LexerOptions::default()).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_lexer);
+criterion_main!(benches);
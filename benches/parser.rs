@@ -0,0 +1,77 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use crusty_json::{lexer, parser, parser_from_iter, ParserOptions};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// System allocator that counts every allocation made through it
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn allocations_during<T>(f: impl FnOnce() -> T) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    drop(f());
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+/// Builds an array of `count` small records heavy on numbers and literals
+fn sample_document(count: usize) -> String {
+    let mut json = String::from("[");
+
+    for i in 0..count {
+        if i > 0 {
+            json.push(',');
+        }
+        json.push_str(&format!(
+            r#"{{"id": {}, "name": "fulano {}", "point": [{}.5, -{}e2, 0], "active": true, "parent": null}}"#,
+            i,
+            i,
+            i % 100,
+            i % 7
+        ));
+    }
+
+    json.push(']');
+    json
+}
+
+fn bench_parser(c: &mut Criterion) {
+    let tokens = lexer(&sample_document(20_000)).unwrap();
+
+    // Parsing borrowed tokens only copies out the strings the values keep,
+    // while cloning them up front copies every number along with them
+    println!(
+        "allocations: parser {}, cloned tokens {}",
+        allocations_during(|| parser(&tokens).unwrap()),
+        allocations_during(
+            || parser_from_iter(tokens.iter().cloned(), &ParserOptions::default()).unwrap()
+        )
+    );
+
+    let mut group = c.benchmark_group("parser");
+    group.throughput(Throughput::Elements(tokens.len() as u64));
+    group.bench_function("records", |b| b.iter(|| parser(&tokens).unwrap()));
+    group.bench_function("records_cloned_tokens", |b| {
+        b.iter(|| parser_from_iter(tokens.iter().cloned(), &ParserOptions::default()).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_parser);
+criterion_main!(benches);
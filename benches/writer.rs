@@ -0,0 +1,49 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use crusty_json::{json, write_json, FormatOptions, JsonValue};
+use std::{
+    env,
+    fs::{self, File},
+    io::{BufWriter, Write},
+};
+
+/// Builds an array of `count` small objects, a few megabytes once pretty-printed
+fn sample_value(count: usize) -> JsonValue {
+    let records = (0..count)
+        .map(
+            |i| json!({"id": i as i64, "name": "fulano", "tags": ["a", "b", null], "active": true}),
+        )
+        .collect();
+    JsonValue::Array(records)
+}
+
+fn bench_writer(c: &mut Criterion) {
+    let value = sample_value(50_000);
+    let options = FormatOptions {
+        indent: Some(2),
+        ..Default::default()
+    };
+    let path = env::temp_dir().join(format!("crusty-json-bench-{}.json", std::process::id()));
+
+    let mut group = c.benchmark_group("writer");
+    group.throughput(Throughput::Elements(50_000));
+    group.sample_size(10);
+    group.bench_function("pretty_unbuffered", |b| {
+        b.iter(|| {
+            let mut file = File::create(&path).unwrap();
+            write_json(&value, &mut file, &options).unwrap();
+        })
+    });
+    group.bench_function("pretty_buffered", |b| {
+        b.iter(|| {
+            let mut file = BufWriter::new(File::create(&path).unwrap());
+            write_json(&value, &mut file, &options).unwrap();
+            file.flush().unwrap();
+        })
+    });
+    group.finish();
+
+    fs::remove_file(path).unwrap();
+}
+
+criterion_group!(benches, bench_writer);
+criterion_main!(benches);
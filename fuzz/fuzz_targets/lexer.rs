@@ -0,0 +1,24 @@
+#![no_main]
+
+use crusty_json::{lexer_bytes, lexer_collect, LexerOptions};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let lenient = LexerOptions {
+        allow_comments: true,
+        allow_single_quotes: true,
+        allow_unquoted_keys: true,
+        allow_non_finite_numbers: true,
+        allow_control_characters: true,
+    };
+
+    for options in [LexerOptions::default(), lenient] {
+        if let Err(err) = lexer_bytes(data, &options) {
+            assert!(err.offset <= data.len());
+        }
+    }
+
+    if let Ok(text) = std::str::from_utf8(data) {
+        lexer_collect(text);
+    }
+});
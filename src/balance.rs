@@ -0,0 +1,120 @@
+use crate::lexer::{JsonToken, Span};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum BalanceError {
+    #[error("Mismatched `{found}` at {at}, expected `{expected}` to close the bracket at {open}")]
+    Mismatched {
+        expected: char,
+        found: char,
+        open: Span,
+        at: Span,
+    },
+    #[error("Unclosed `{0}` at {1}")]
+    Unclosed(char, Span),
+    #[error("Unexpected `{0}` at {1} without a bracket to close")]
+    Unexpected(char, Span),
+}
+
+/// Checks that every bracket is closed by the matching one, pointing right at
+/// the offending bracket otherwise. Brackets inside strings are part of their
+/// token, so they never count
+pub fn check_balanced(tokens: &[(JsonToken, Span)]) -> Result<(), BalanceError> {
+    let mut open: Vec<(char, Span)> = Vec::new();
+
+    for (token, span) in tokens {
+        let found = match token {
+            JsonToken::OpenCurlyBracket => {
+                open.push(('}', *span));
+                continue;
+            }
+            JsonToken::OpenSquareBracket => {
+                open.push((']', *span));
+                continue;
+            }
+            JsonToken::CloseCurlyBracket => '}',
+            JsonToken::CloseSquareBracket => ']',
+            _ => continue,
+        };
+
+        match open.pop() {
+            Some((expected, _)) if expected == found => {}
+            Some((expected, open_span)) => {
+                return Err(BalanceError::Mismatched {
+                    expected,
+                    found,
+                    open: open_span,
+                    at: *span,
+                })
+            }
+            None => return Err(BalanceError::Unexpected(found, *span)),
+        }
+    }
+
+    // The innermost unclosed bracket is the one missing its pair first
+    match open.pop() {
+        Some(('}', span)) => Err(BalanceError::Unclosed('{', span)),
+        Some((_, span)) => Err(BalanceError::Unclosed('[', span)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_balanced, BalanceError};
+    use crate::lexer::{lexer, Span};
+
+    fn check(input: &str) -> Result<(), BalanceError> {
+        check_balanced(&lexer(input).unwrap())
+    }
+
+    #[test]
+    fn test_balanced() {
+        assert_eq!(check(r#"{"a": [1, {"b": []}], "c": "}]"}"#), Ok(()));
+        assert_eq!(check(""), Ok(()));
+        // Only brackets are checked, not the rest of the syntax
+        assert_eq!(check("[1 2 : ,]"), Ok(()));
+    }
+
+    #[test]
+    fn test_mismatched() {
+        let err = check("{[}]").unwrap_err();
+
+        assert_eq!(
+            err,
+            BalanceError::Mismatched {
+                expected: ']',
+                found: '}',
+                open: Span { line: 1, col: 2 },
+                at: Span { line: 1, col: 3 },
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "Mismatched `}` at line 1, column 3, expected `]` to close the bracket at line 1, column 2"
+        );
+    }
+
+    #[test]
+    fn test_unclosed() {
+        assert_eq!(
+            check("{\"a\": [1, 2]\n"),
+            Err(BalanceError::Unclosed('{', Span { line: 1, col: 1 }))
+        );
+        assert_eq!(
+            check("[{}, [\"[\"]"),
+            Err(BalanceError::Unclosed('[', Span { line: 1, col: 1 }))
+        );
+    }
+
+    #[test]
+    fn test_surplus_closing_bracket() {
+        let err = check("[1, 2]]").unwrap_err();
+
+        assert_eq!(err, BalanceError::Unexpected(']', Span { line: 1, col: 7 }));
+        assert_eq!(
+            err.to_string(),
+            "Unexpected `]` at line 1, column 7 without a bracket to close"
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use crate::{
+    number::JsonNumber,
+    parser::{JsonObject, JsonValue},
+};
+use std::collections::HashMap;
+
+impl From<&str> for JsonValue {
+    fn from(value: &str) -> Self {
+        JsonValue::String(value.to_string())
+    }
+}
+
+impl From<String> for JsonValue {
+    fn from(value: String) -> Self {
+        JsonValue::String(value)
+    }
+}
+
+macro_rules! impl_from_number {
+    ($($number:ty),*) => {
+        $(
+            impl From<$number> for JsonValue {
+                fn from(value: $number) -> Self {
+                    JsonValue::Number(JsonNumber::from(value))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_number!(f64, f32, i64, i32, u64, u32);
+
+impl From<bool> for JsonValue {
+    fn from(value: bool) -> Self {
+        JsonValue::Boolean(value)
+    }
+}
+
+impl From<()> for JsonValue {
+    fn from(_: ()) -> Self {
+        JsonValue::Null
+    }
+}
+
+impl From<Vec<JsonValue>> for JsonValue {
+    fn from(value: Vec<JsonValue>) -> Self {
+        JsonValue::Array(value)
+    }
+}
+
+impl From<JsonObject> for JsonValue {
+    fn from(value: JsonObject) -> Self {
+        JsonValue::Object(value)
+    }
+}
+
+/// Members end up in the map's iteration order, which `HashMap` leaves unspecified
+impl From<HashMap<String, JsonValue>> for JsonValue {
+    fn from(value: HashMap<String, JsonValue>) -> Self {
+        JsonValue::Object(value.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::parser::{JsonObject, JsonValue};
+
+    #[test]
+    fn test_from_strings() {
+        assert_eq!(
+            JsonValue::from("fulano"),
+            JsonValue::String("fulano".into())
+        );
+        assert_eq!(
+            JsonValue::from(String::from("fulano")),
+            JsonValue::String("fulano".into())
+        );
+    }
+
+    #[test]
+    fn test_from_numbers() {
+        assert_eq!(JsonValue::from(2.5), JsonValue::Number(2.5.into()));
+        assert_eq!(JsonValue::from(-20i64), JsonValue::Number((-20).into()));
+        assert_eq!(JsonValue::from(20), JsonValue::Number(20.into()));
+        assert_eq!(JsonValue::from(20u64), JsonValue::Number(20.into()));
+    }
+
+    #[test]
+    fn test_from_bool_and_unit() {
+        assert_eq!(JsonValue::from(true), JsonValue::Boolean(true));
+        assert_eq!(JsonValue::from(false), JsonValue::Boolean(false));
+        assert_eq!(JsonValue::from(()), JsonValue::Null);
+    }
+
+    #[test]
+    fn test_from_vec() {
+        let value = JsonValue::from(vec![JsonValue::from(1), JsonValue::from("a")]);
+
+        assert_eq!(
+            value,
+            JsonValue::Array(vec![
+                JsonValue::Number(1.into()),
+                JsonValue::String("a".into())
+            ])
+        );
+    }
+
+    #[test]
+    fn test_from_maps() {
+        let expected = || {
+            let mut obj = JsonObject::new();
+            obj.insert("a".into(), JsonValue::Number(1.into()));
+            obj
+        };
+
+        let mut map = HashMap::new();
+        map.insert("a".to_string(), JsonValue::from(1));
+
+        assert_eq!(JsonValue::from(map), JsonValue::Object(expected()));
+        assert_eq!(JsonValue::from(expected()), JsonValue::Object(expected()));
+    }
+}
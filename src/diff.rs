@@ -0,0 +1,221 @@
+use crate::{parser::JsonValue, pointer::escape_token};
+use std::fmt;
+
+/// What happened to the value at a given path
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    /// Only the second document has a value there
+    Added(JsonValue),
+    /// Only the first document has a value there
+    Removed(JsonValue),
+    /// Both documents have a value there but they differ
+    Changed { old: JsonValue, new: JsonValue },
+}
+
+/// Single difference found by [`json_diff`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference {
+    /// JSON Pointer to the value that differs
+    pub path: String,
+    pub change: Change,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "(root)"
+        } else {
+            &self.path
+        };
+
+        match &self.change {
+            Change::Added(new) => write!(f, "{}: added {}", path, new),
+            Change::Removed(old) => write!(f, "{}: removed {}", path, old),
+            Change::Changed { old, new } => write!(f, "{}: changed {} -> {}", path, old, new),
+        }
+    }
+}
+
+fn diff_into(path: String, a: &JsonValue, b: &JsonValue, out: &mut Vec<Difference>) {
+    match (a, b) {
+        (JsonValue::Object(a), JsonValue::Object(b)) => {
+            for (key, old) in a {
+                let path = format!("{}/{}", path, escape_token(key));
+                match b.get(key) {
+                    Some(new) => diff_into(path, old, new, out),
+                    None => out.push(Difference {
+                        path,
+                        change: Change::Removed(old.clone()),
+                    }),
+                }
+            }
+
+            for (key, new) in b.iter().filter(|(key, _)| !a.contains_key(*key)) {
+                out.push(Difference {
+                    path: format!("{}/{}", path, escape_token(key)),
+                    change: Change::Added(new.clone()),
+                });
+            }
+        }
+        (JsonValue::Array(a), JsonValue::Array(b)) => {
+            for i in 0..a.len().max(b.len()) {
+                let path = format!("{}/{}", path, i);
+                match (a.get(i), b.get(i)) {
+                    (Some(old), Some(new)) => diff_into(path, old, new, out),
+                    (Some(old), None) => out.push(Difference {
+                        path,
+                        change: Change::Removed(old.clone()),
+                    }),
+                    (None, Some(new)) => out.push(Difference {
+                        path,
+                        change: Change::Added(new.clone()),
+                    }),
+                    (None, None) => unreachable!("index is below the longest length"),
+                }
+            }
+        }
+        _ if !a.value_eq(b) => out.push(Difference {
+            path,
+            change: Change::Changed {
+                old: a.clone(),
+                new: b.clone(),
+            },
+        }),
+        _ => {}
+    }
+}
+
+/// Lists what changed from `a` to `b`, comparing objects key by key and
+/// arrays index by index. Equal documents give no differences, where numbers
+/// count as equal when their values are, like [`JsonValue::value_eq`]
+pub fn json_diff(a: &JsonValue, b: &JsonValue) -> Vec<Difference> {
+    let mut out = Vec::new();
+    diff_into(String::new(), a, b, &mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{json_diff, Change, Difference};
+    use crate::parser::JsonValue;
+
+    #[test]
+    fn test_no_differences() {
+        let a = json!({"a": [1, {"b": null}], "c": "x"});
+        let b = json!({"c": "x", "a": [1, {"b": null}]});
+
+        assert!(json_diff(&a, &b).is_empty());
+    }
+
+    #[test]
+    #[cfg(not(feature = "btreemap"))]
+    fn test_added_and_removed_keys() {
+        let a = json!({"name": "fulano", "old": true, "nested": {"x": 1}});
+        let b = json!({"name": "fulano", "nested": {"x": 1, "a/b": 2}, "new": null});
+
+        assert_eq!(
+            json_diff(&a, &b),
+            vec![
+                Difference {
+                    path: "/old".into(),
+                    change: Change::Removed(json!(true)),
+                },
+                Difference {
+                    path: "/nested/a~1b".into(),
+                    change: Change::Added(json!(2)),
+                },
+                Difference {
+                    path: "/new".into(),
+                    change: Change::Added(json!(null)),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_numbers_compare_by_value() {
+        let a = json!({"n": 1, "list": [2.0, f64::NAN]});
+        let b = json!({"n": 1.0, "list": [2, f64::NAN]});
+
+        assert!(json_diff(&a, &b).is_empty());
+        assert_eq!(json_diff(&json!(1), &json!(1.5)).len(), 1);
+    }
+
+    #[test]
+    fn test_changed_scalar() {
+        let a = json!({"server": {"ports": [80, 443], "host": "a"}});
+        let b = json!({"server": {"ports": [80, 8443], "host": "a"}});
+
+        assert_eq!(
+            json_diff(&a, &b),
+            vec![Difference {
+                path: "/server/ports/1".into(),
+                change: Change::Changed {
+                    old: json!(443),
+                    new: json!(8443),
+                },
+            }]
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "btreemap"))]
+    fn test_array_lengths_and_types() {
+        let a = json!({"list": [1, 2, 3], "kind": [1]});
+        let b = json!({"list": [1], "kind": {"0": 1}});
+
+        assert_eq!(
+            json_diff(&a, &b),
+            vec![
+                Difference {
+                    path: "/list/1".into(),
+                    change: Change::Removed(json!(2)),
+                },
+                Difference {
+                    path: "/list/2".into(),
+                    change: Change::Removed(json!(3)),
+                },
+                Difference {
+                    path: "/kind".into(),
+                    change: Change::Changed {
+                        old: json!([1]),
+                        new: json!({"0": 1}),
+                    },
+                },
+            ]
+        );
+        assert_eq!(
+            json_diff(&json!([]), &json!(null)),
+            vec![Difference {
+                path: "".into(),
+                change: Change::Changed {
+                    old: json!([]),
+                    new: json!(null),
+                },
+            }]
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let a = json!({"a": 1, "b": [true], "c": "x"});
+        let b = json!({"a": 2, "b": [], "d": null});
+        let lines: Vec<String> = json_diff(&a, &b).iter().map(|d| d.to_string()).collect();
+
+        assert_eq!(
+            lines,
+            [
+                "/a: changed 1 -> 2",
+                "/b/0: removed true",
+                "/c: removed \"x\"",
+                "/d: added null",
+            ]
+        );
+        assert_eq!(
+            json_diff(&JsonValue::Null, &json!([]))
+                .remove(0)
+                .to_string(),
+            "(root): changed null -> []"
+        );
+    }
+}
@@ -0,0 +1,27 @@
+use crate::{lexer::LexError, parser::JsonParseError};
+use std::io;
+use thiserror::Error;
+
+/// Any error found while turning text into a [`JsonValue`](crate::JsonValue)
+#[derive(Error, Debug)]
+pub enum JsonError {
+    #[error(transparent)]
+    Token(#[from] LexError),
+    #[error(transparent)]
+    Parse(#[from] JsonParseError),
+    /// Reading the input failed, or it wasn't valid UTF-8
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// I/O errors can't be compared, so they're only told apart by their kind
+impl PartialEq for JsonError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (JsonError::Token(a), JsonError::Token(b)) => a == b,
+            (JsonError::Parse(a), JsonError::Parse(b)) => a == b,
+            (JsonError::Io(a), JsonError::Io(b)) => a.kind() == b.kind(),
+            _ => false,
+        }
+    }
+}
@@ -1,164 +1,944 @@
+use std::{borrow::Cow, fmt, iter::Peekable, str::CharIndices};
 use thiserror::Error;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum JsonToken {
     String(String),
     Number(String),
-    Boolean(String),
-    Null(String),
+    True,
+    False,
+    Null,
     OpenCurlyBracket,
     CloseCurlyBracket,
     OpenSquareBracket,
     CloseSquareBracket,
     Colon,
     Comma,
+    /// Bare object key, only produced with [`LexerOptions::allow_unquoted_keys`]
+    Identifier(String),
+}
+
+/// Token borrowing its text from the input, only strings with escape
+/// sequences own their contents
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonTokenRef<'a> {
+    String(Cow<'a, str>),
+    Number(&'a str),
+    True,
+    False,
+    Null,
+    OpenCurlyBracket,
+    CloseCurlyBracket,
+    OpenSquareBracket,
+    CloseSquareBracket,
+    Colon,
+    Comma,
+    Identifier(&'a str),
+}
+
+impl JsonTokenRef<'_> {
+    pub fn into_owned(self) -> JsonToken {
+        match self {
+            JsonTokenRef::String(json_string) => JsonToken::String(json_string.into_owned()),
+            JsonTokenRef::Number(json_number) => JsonToken::Number(json_number.to_string()),
+            JsonTokenRef::True => JsonToken::True,
+            JsonTokenRef::False => JsonToken::False,
+            JsonTokenRef::Null => JsonToken::Null,
+            JsonTokenRef::OpenCurlyBracket => JsonToken::OpenCurlyBracket,
+            JsonTokenRef::CloseCurlyBracket => JsonToken::CloseCurlyBracket,
+            JsonTokenRef::OpenSquareBracket => JsonToken::OpenSquareBracket,
+            JsonTokenRef::CloseSquareBracket => JsonToken::CloseSquareBracket,
+            JsonTokenRef::Colon => JsonToken::Colon,
+            JsonTokenRef::Comma => JsonToken::Comma,
+            JsonTokenRef::Identifier(name) => JsonToken::Identifier(name.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub line: usize,
+    pub col: usize,
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.col)
+    }
 }
 
 #[derive(Error, Debug, PartialEq)]
 pub enum JsonTokenError {
-    #[error("Expected end-of-string")]
-    ExpectedEndOfString,
+    #[error("Unterminated string starting at {0}")]
+    ExpectedEndOfString(Span),
+    #[error("Expected escape sequence, got end-of-input")]
+    UnterminatedEscape,
+    #[error("Invalid escape sequence, got `\\{0}`")]
+    InvalidEscape(char),
+    #[error("Invalid unicode escape, got `\\u{0}`")]
+    InvalidUnicodeEscape(String),
+    #[error("Unpaired surrogate in unicode escape, got `\\u{0:04X}`")]
+    UnpairedSurrogate(u16),
     #[error("Invalid token, got `{0}`")]
     InvalidToken(char),
+    #[error("Invalid literal `{0}`, expected `true`, `false` or `null`")]
+    InvalidLiteral(String),
+    #[error("Unexpected colon after number `{0}`")]
+    UnexpectedColonAfterNumber(String),
+    #[error("Invalid number `{0}`, {1}")]
+    InvalidNumber(String, JsonNumberError),
+    #[error("Expected end-of-comment")]
+    ExpectedEndOfComment,
+    #[error("`{0}` is not valid JSON, non-finite numbers must be enabled to accept it")]
+    NonFiniteNumber(String),
+    #[error("Unescaped control character U+{:04X} in string at {1}", *.0 as u32)]
+    ControlCharacterInString(char, Span),
+    #[error("Invalid UTF-8 sequence")]
+    InvalidUtf8,
+}
+
+/// Error from any of the lexer entry points along with where lexing failed
+#[derive(Error, Debug, PartialEq)]
+#[error("{error} (byte {offset})")]
+pub struct LexError {
+    pub error: JsonTokenError,
+    /// Byte offset into the input, byte-order mark included, of the first byte
+    /// that couldn't be lexed. Input that ends too early fails right past its end
+    pub offset: usize,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum JsonNumberError {
+    #[error("expected digit before fraction or exponent")]
+    MissingIntegerDigits,
+    #[error("expected digit after minus sign")]
+    MissingDigitAfterMinus,
+    #[error("leading zeros are not allowed")]
+    LeadingZero,
+    #[error("expected digit after decimal point")]
+    MissingFractionDigits,
+    #[error("expected digit in exponent")]
+    MissingExponentDigits,
+    #[error("unexpected `{0}`")]
+    UnexpectedChar(char),
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct LexerOptions {
+    /// Skip `//` line comments and `/* */` block comments like JSONC does
+    pub allow_comments: bool,
+    /// Accept strings between single quotes like JavaScript does, where `\'`
+    /// escapes a single quote
+    pub allow_single_quotes: bool,
+    /// Accept object keys written as bare identifiers like `{name: "x"}`
+    pub allow_unquoted_keys: bool,
+    /// Accept `NaN`, `Infinity` and `-Infinity` as numbers. Standard JSON has no
+    /// way to write them, so they don't round-trip: serializing turns them into `null`
+    pub allow_non_finite_numbers: bool,
+    /// Accept raw control characters like tabs and newlines inside strings,
+    /// which standard JSON only allows escaped
+    pub allow_control_characters: bool,
+}
+
+/// Byte cursor over the input that keeps track of the line and column of the
+/// next character, where columns count characters rather than bytes
+#[derive(Clone)]
+struct Cursor<'a> {
+    src: &'a str,
+    pos: usize,
+    line: usize,
+    col: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(src: &'a str) -> Self {
+        Self {
+            src,
+            // A UTF-8 byte-order mark is only meaningful at the very start of
+            // the input, and is skipped without counting as a column
+            pos: if src.starts_with('\u{FEFF}') {
+                '\u{FEFF}'.len_utf8()
+            } else {
+                0
+            },
+            line: 1,
+            col: 1,
+        }
+    }
+
+    /// Error at the byte the cursor is on
+    fn error(&self, error: JsonTokenError) -> LexError {
+        LexError {
+            error,
+            offset: self.pos,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.src.as_bytes().get(self.pos).copied()
+    }
+
+    /// Decodes the full character at the cursor, only needed off the ASCII fast path
+    fn peek_char(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.peek_char()?;
+        self.pos += c.len_utf8();
+
+        if c == '\n' {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+
+        Some(c)
+    }
+
+    /// Input consumed since `start`, which must sit on a character boundary
+    fn slice(&self, start: usize) -> &'a str {
+        &self.src[start..self.pos]
+    }
+
+    /// Consumes bytes up to the next one matching `stop`, returning them. `stop`
+    /// must only match ASCII bytes so the cursor stays on a character boundary
+    fn take_until(&mut self, stop: impl Fn(u8) -> bool) -> &'a str {
+        let start = self.pos;
+        let rest = &self.src.as_bytes()[start..];
+        let len = rest.iter().position(|&b| stop(b)).unwrap_or(rest.len());
+
+        for &b in &rest[..len] {
+            self.track(b);
+        }
+        self.pos += len;
+
+        self.slice(start)
+    }
+
+    fn track(&mut self, b: u8) {
+        if b == b'\n' {
+            self.line += 1;
+            self.col = 1;
+        } else if b & 0xC0 != 0x80 {
+            // UTF-8 continuation bytes don't start a new character
+            self.col += 1;
+        }
+    }
+
+    fn span(&self) -> Span {
+        Span {
+            line: self.line,
+            col: self.col,
+        }
+    }
+}
+
+impl Iterator for Cursor<'_> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        let b = self.peek()?;
+        self.pos += 1;
+        self.track(b);
+
+        Some(b)
+    }
+}
+
+fn is_number_char(b: u8) -> bool {
+    matches!(b, b'-' | b'.' | b'0'..=b'9' | b'e' | b'E' | b'+')
+}
+
+fn skip_digits(chars: &mut Peekable<CharIndices>) -> usize {
+    let mut count = 0;
+
+    while let Some((_, c)) = chars.peek() {
+        if !c.is_ascii_digit() {
+            break;
+        }
+        chars.next();
+        count += 1;
+    }
+
+    count
+}
+
+/// Checks `number` against the JSON number grammar, failing with the byte
+/// offset into it where it goes wrong
+pub(crate) fn validate_number(number: &str) -> Result<(), (JsonNumberError, usize)> {
+    let mut chars = number.char_indices().peekable();
+    let at = |chars: &mut Peekable<CharIndices>| chars.peek().map_or(number.len(), |&(i, _)| i);
+
+    let negative = chars.peek().map(|&(_, c)| c) == Some('-');
+    if negative {
+        chars.next();
+    }
+
+    let first = at(&mut chars);
+    match chars.next() {
+        Some((_, '0')) => {
+            if let Some((i, c)) = chars.peek() {
+                if c.is_ascii_digit() {
+                    return Err((JsonNumberError::LeadingZero, *i));
+                }
+            }
+        }
+        Some((_, '1'..='9')) => {
+            skip_digits(&mut chars);
+        }
+        _ if negative => {
+            return Err((JsonNumberError::MissingDigitAfterMinus, first));
+        }
+        _ => {
+            return Err((JsonNumberError::MissingIntegerDigits, first));
+        }
+    }
+
+    if let Some((_, '.')) = chars.peek() {
+        chars.next();
+
+        if skip_digits(&mut chars) == 0 {
+            return Err((JsonNumberError::MissingFractionDigits, at(&mut chars)));
+        }
+    }
+
+    if let Some((_, 'e' | 'E')) = chars.peek() {
+        chars.next();
+
+        if let Some((_, '+' | '-')) = chars.peek() {
+            chars.next();
+        }
+
+        if skip_digits(&mut chars) == 0 {
+            return Err((JsonNumberError::MissingExponentDigits, at(&mut chars)));
+        }
+    }
+
+    match chars.next() {
+        Some((i, c)) => Err((JsonNumberError::UnexpectedChar(c), i)),
+        None => Ok(()),
+    }
+}
+
+fn lex_hex_escape(chars: &mut Cursor) -> Result<u16, LexError> {
+    let start = chars.pos;
+    let mut hex = String::new();
+
+    for _ in 0..4 {
+        let hex_c = chars
+            .next_char()
+            .ok_or_else(|| chars.error(JsonTokenError::UnterminatedEscape))?;
+        hex.push(hex_c);
+    }
+
+    if let Some(i) = hex.find(|c: char| !c.is_ascii_hexdigit()) {
+        return Err(LexError {
+            error: JsonTokenError::InvalidUnicodeEscape(hex),
+            offset: start + i,
+        });
+    }
+
+    Ok(u16::from_str_radix(&hex, 16).expect("four hex digits fit in a u16"))
+}
+
+/// Reads the hex digits of a `\u` escape whose `\u` was already consumed
+fn lex_unicode_escape(chars: &mut Cursor) -> Result<char, LexError> {
+    let start = chars.pos - 2;
+    let code = lex_hex_escape(chars)?;
+    let unpaired = |offset| LexError {
+        error: JsonTokenError::UnpairedSurrogate(code),
+        offset,
+    };
+
+    let code_point = match code {
+        0xD800..=0xDBFF => {
+            // A high surrogate must be immediately followed by an escaped low surrogate
+            let low_start = chars.pos;
+            if chars.peek() != Some(b'\\') {
+                return Err(unpaired(low_start));
+            }
+            chars.next();
+
+            if chars.peek() != Some(b'u') {
+                return Err(unpaired(chars.pos));
+            }
+            chars.next();
+
+            let low = lex_hex_escape(chars)?;
+            if !(0xDC00..=0xDFFF).contains(&low) {
+                return Err(unpaired(low_start));
+            }
+
+            0x10000 + ((code as u32 - 0xD800) << 10) + (low as u32 - 0xDC00)
+        }
+        0xDC00..=0xDFFF => {
+            return Err(unpaired(start));
+        }
+        _ => code as u32,
+    };
+
+    Ok(char::from_u32(code_point).expect("surrogates are handled above"))
+}
+
+fn lex_escape(chars: &mut Cursor, quote: u8) -> Result<char, LexError> {
+    let at = chars.pos;
+    let escaped = chars
+        .next_char()
+        .ok_or_else(|| chars.error(JsonTokenError::UnterminatedEscape))?;
+
+    match escaped {
+        '"' => Ok('"'),
+        '\'' if quote == b'\'' => Ok('\''),
+        '\\' => Ok('\\'),
+        '/' => Ok('/'),
+        'b' => Ok('\u{0008}'),
+        'f' => Ok('\u{000C}'),
+        'n' => Ok('\n'),
+        'r' => Ok('\r'),
+        't' => Ok('\t'),
+        'u' => lex_unicode_escape(chars),
+        _ => Err(LexError {
+            error: JsonTokenError::InvalidEscape(escaped),
+            offset: at,
+        }),
+    }
+}
+
+/// Reads a string whose opening `quote` was already consumed at `start`,
+/// borrowing it from the input unless it contains escape sequences
+fn lex_string<'a>(
+    chars: &mut Cursor<'a>,
+    start: Span,
+    quote: u8,
+    options: &LexerOptions,
+) -> Result<Cow<'a, str>, LexError> {
+    let stop = |b: u8| b == quote || b == b'\\' || (b < 0x20 && !options.allow_control_characters);
+    // Whatever stopped the run of plain characters, unless it ends the string
+    let unexpected = |chars: &Cursor, b: Option<u8>| match b {
+        Some(b) => chars.error(JsonTokenError::ControlCharacterInString(
+            b as char,
+            chars.span(),
+        )),
+        None => chars.error(JsonTokenError::ExpectedEndOfString(start)),
+    };
+
+    let run = chars.take_until(stop);
+
+    let mut json_string = match chars.peek() {
+        Some(b) if b == quote => {
+            chars.next();
+            return Ok(Cow::Borrowed(run));
+        }
+        Some(b'\\') => run.to_string(),
+        b => return Err(unexpected(chars, b)),
+    };
+
+    loop {
+        chars.next();
+        json_string.push(lex_escape(chars, quote)?);
+        json_string.push_str(chars.take_until(stop));
+
+        match chars.peek() {
+            Some(b) if b == quote => {
+                chars.next();
+                return Ok(Cow::Owned(json_string));
+            }
+            Some(b'\\') => {}
+            b => return Err(unexpected(chars, b)),
+        }
+    }
 }
 
-fn is_number_char(c: char) -> bool {
-    match c {
-        '-' | '.' | '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => true,
+fn is_delimiter_char(b: u8, options: &LexerOptions) -> bool {
+    match b {
+        b',' | b'}' | b']' | b' ' | b'\n' | b'\r' | b'\t' => true,
+        b'/' => options.allow_comments,
         _ => false,
     }
 }
 
-fn check_end_of_token_value(c: char) -> Option<JsonToken> {
-    match c {
-        ',' => Some(JsonToken::Comma),
-        '}' => Some(JsonToken::CloseCurlyBracket),
-        ']' => Some(JsonToken::CloseSquareBracket),
-        _ => None,
+/// Fails on a character right after a token that keeps it from ending there,
+/// where `colon` lets a colon follow as well
+fn expect_delimiter(chars: &Cursor, options: &LexerOptions, colon: bool) -> Result<(), LexError> {
+    match chars.peek() {
+        Some(b) if is_delimiter_char(b, options) || (colon && b == b':') => Ok(()),
+        Some(_) => Err(chars.error(JsonTokenError::InvalidToken(
+            chars.peek_char().expect("peeked a byte"),
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Reads the rest of a `true`, `false` or `null` literal starting at `start`
+fn lex_literal(
+    chars: &mut Cursor,
+    start: usize,
+    literal: &str,
+    options: &LexerOptions,
+) -> Result<(), LexError> {
+    chars.take_until(|b| !b.is_ascii_alphanumeric());
+
+    let word = chars.slice(start);
+    if word != literal {
+        // Lexing fails at the first character that doesn't match
+        let matching = word
+            .bytes()
+            .zip(literal.bytes())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        return Err(LexError {
+            error: JsonTokenError::InvalidLiteral(word.to_string()),
+            offset: start + matching,
+        });
+    }
+
+    expect_delimiter(chars, options, true)
+}
+
+/// Reads a bare word starting at `start`, telling the literals apart from
+/// identifiers like `[A-Za-z_][A-Za-z0-9_]*`
+fn lex_identifier<'a>(
+    chars: &mut Cursor<'a>,
+    start: usize,
+    options: &LexerOptions,
+) -> Result<JsonTokenRef<'a>, LexError> {
+    chars.take_until(|b| !b.is_ascii_alphanumeric() && b != b'_');
+
+    let token = match chars.slice(start) {
+        "true" => JsonTokenRef::True,
+        "false" => JsonTokenRef::False,
+        "null" => JsonTokenRef::Null,
+        name => JsonTokenRef::Identifier(name),
+    };
+
+    expect_delimiter(chars, options, true)?;
+    Ok(token)
+}
+
+/// Whether the input at `start` spells out exactly `NaN`, `Infinity` or `-Infinity`
+fn is_non_finite_at(chars: &Cursor, start: usize) -> bool {
+    let rest = &chars.src[start..];
+    let sign = usize::from(rest.starts_with('-'));
+    let len = sign
+        + rest.as_bytes()[sign..]
+            .iter()
+            .take_while(|b| b.is_ascii_alphanumeric() || **b == b'_')
+            .count();
+
+    matches!(&rest[..len], "NaN" | "Infinity" | "-Infinity")
+}
+
+/// Reads the rest of a non-finite number starting at `start`, which lexes
+/// into a regular number token that parses into the matching `f64`
+fn lex_non_finite<'a>(
+    chars: &mut Cursor<'a>,
+    start: usize,
+    options: &LexerOptions,
+) -> Result<JsonTokenRef<'a>, LexError> {
+    chars.take_until(|b| !b.is_ascii_alphanumeric() && b != b'_');
+    let json_number = chars.slice(start);
+
+    if !options.allow_non_finite_numbers {
+        return Err(LexError {
+            error: JsonTokenError::NonFiniteNumber(json_number.to_string()),
+            offset: start,
+        });
     }
+
+    expect_delimiter(chars, options, false)?;
+    Ok(JsonTokenRef::Number(json_number))
 }
 
-pub fn lexer(raw: String) -> Result<Vec<JsonToken>, JsonTokenError> {
-    let mut vec: Vec<JsonToken> = vec![];
+/// Skips a comment whose leading `/` was already consumed
+fn skip_comment(chars: &mut Cursor) -> Result<(), LexError> {
+    match chars.peek() {
+        Some(b'/') => {
+            chars.next();
+            for b in chars.by_ref() {
+                if b == b'\n' {
+                    break;
+                }
+            }
+        }
+        Some(b'*') => {
+            chars.next();
+            let mut prev = None;
+
+            loop {
+                match chars.next() {
+                    Some(b'/') if prev == Some(b'*') => break,
+                    Some(b) => prev = Some(b),
+                    None => return Err(chars.error(JsonTokenError::ExpectedEndOfComment)),
+                }
+            }
+        }
+        _ => {
+            return Err(LexError {
+                error: JsonTokenError::InvalidToken('/'),
+                offset: chars.pos - 1,
+            });
+        }
+    }
+
+    Ok(())
+}
 
-    let mut chars = raw.chars();
+/// Turns JSONC into plain JSON by blanking out `//` and `/* */` comments,
+/// leaving anything that looks like one inside a string alone. Every comment
+/// character becomes a space and newlines stay, so lines and columns of the
+/// remaining text don't move. An unterminated block comment runs to the end
+/// of the input
+pub fn strip_comments(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
 
     while let Some(c) = chars.next() {
         match c {
-            '{' => vec.push(JsonToken::OpenCurlyBracket),
-            '}' => vec.push(JsonToken::CloseCurlyBracket),
-            '[' => vec.push(JsonToken::OpenSquareBracket),
-            ']' => vec.push(JsonToken::CloseSquareBracket),
-            ':' => vec.push(JsonToken::Colon),
-            ',' => vec.push(JsonToken::Comma),
             '"' => {
-                let mut json_string = String::new();
-
-                let mut done = false;
-                while let Some(str_c) = chars.next() {
-                    if str_c != '"' {
-                        json_string.push(str_c);
-                    } else {
-                        done = true;
-                        break;
+                out.push(c);
+                while let Some(c) = chars.next() {
+                    out.push(c);
+                    match c {
+                        '\\' => out.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
                     }
                 }
-
-                if !done {
-                    return Err(JsonTokenError::ExpectedEndOfString);
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                out.push(' ');
+                while chars.next_if(|&c| c != '\n').is_some() {
+                    out.push(' ');
                 }
-
-                vec.push(JsonToken::String(json_string));
             }
-            'f' => {
-                let false_len = 5;
-                let mut json_false = String::from(c);
-
-                let mut letter_count = 1;
-                while letter_count < false_len {
-                    if let Some(false_c) = chars.next() {
-                        json_false.push(false_c);
-                    } else {
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                out.push_str("  ");
+                while let Some(c) = chars.next() {
+                    if c == '*' && chars.next_if_eq(&'/').is_some() {
+                        out.push_str("  ");
                         break;
                     }
-                    letter_count += 1;
+                    out.push(if matches!(c, '\n' | '\r') { c } else { ' ' });
                 }
+            }
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+pub fn lexer(raw: &str) -> Result<Vec<(JsonToken, Span)>, LexError> {
+    lexer_with_options(raw, &LexerOptions::default())
+}
+
+/// Lexes with either [`LexerOptions`] or the lexer half of
+/// [`ParseOptions`](crate::ParseOptions)
+pub fn lexer_with_options(
+    raw: &str,
+    options: &impl AsRef<LexerOptions>,
+) -> Result<Vec<(JsonToken, Span)>, LexError> {
+    Lexer::new(raw, options.as_ref())
+        .map(|token| token.map(|(token, span)| (token.into_owned(), span)))
+        .collect()
+}
+
+/// Splits the input into tokens without their spans, for building parsers
+/// or highlighters of your own on top of the lexer
+///
+/// ```
+/// use crusty_json::{tokenize, JsonToken};
+///
+/// let tokens = tokenize(r#"{"a": [1, null]}"#).unwrap();
+///
+/// assert_eq!(
+///     tokens,
+///     vec![
+///         JsonToken::OpenCurlyBracket,
+///         JsonToken::String("a".into()),
+///         JsonToken::Colon,
+///         JsonToken::OpenSquareBracket,
+///         JsonToken::Number("1".into()),
+///         JsonToken::Comma,
+///         JsonToken::Null,
+///         JsonToken::CloseSquareBracket,
+///         JsonToken::CloseCurlyBracket,
+///     ]
+/// );
+/// ```
+pub fn tokenize(input: &str) -> Result<Vec<JsonToken>, LexError> {
+    Lexer::new(input, &LexerOptions::default())
+        .map(|token| token.map(|(token, _)| token.into_owned()))
+        .collect()
+}
+
+/// Same as [`lexer_with_options`], but string and number tokens point into
+/// `raw` instead of being copied
+pub fn lexer_borrowed<'a>(
+    raw: &'a str,
+    options: &impl AsRef<LexerOptions>,
+) -> Result<Vec<(JsonTokenRef<'a>, Span)>, LexError> {
+    Lexer::new(raw, options.as_ref()).collect()
+}
+
+/// Lexes raw bytes that may not even be valid UTF-8, like fuzzer input. It
+/// never panics, and invalid UTF-8 fails at the first byte of the bad sequence
+pub fn lexer_bytes(
+    raw: &[u8],
+    options: &impl AsRef<LexerOptions>,
+) -> Result<Vec<(JsonToken, Span)>, LexError> {
+    let raw = std::str::from_utf8(raw).map_err(|err| LexError {
+        error: JsonTokenError::InvalidUtf8,
+        offset: err.valid_up_to(),
+    })?;
+
+    lexer_with_options(raw, options)
+}
 
-                vec.push(JsonToken::Boolean(json_false));
+/// Lexes the whole input like [`lexer`], but carries on past invalid tokens
+/// to report every error found along with where the offending token starts
+pub fn lexer_collect(raw: &str) -> (Vec<JsonToken>, Vec<(LexError, Span)>) {
+    let options = LexerOptions::default();
+    let mut chars = Cursor::new(raw);
+    let mut tokens = Vec::new();
+    let mut errors = Vec::new();
+
+    loop {
+        chars.take_until(|b| !matches!(b, b' ' | b'\n' | b'\r' | b'\t'));
+        let before = chars.clone();
+
+        match next_token(&mut chars, &options) {
+            Ok(Some((token, _))) => tokens.push(token.into_owned()),
+            Ok(None) => break,
+            Err(err) => {
+                errors.push((err, before.span()));
+                skip_invalid_token(&mut chars, before);
             }
-            't' => {
-                let true_len = 4;
-                let mut json_true = String::from(c);
-
-                let mut letter_count = 1;
-                while letter_count < true_len {
-                    if let Some(true_c) = chars.next() {
-                        json_true.push(true_c);
-                    } else {
-                        break;
-                    }
-                    letter_count += 1;
-                }
+        }
+    }
+
+    (tokens, errors)
+}
+
+/// Moves past the rest of an invalid token that started at `start`, to the
+/// next place a valid token could begin
+fn skip_invalid_token<'a>(chars: &mut Cursor<'a>, start: Cursor<'a>) {
+    if start.peek() != Some(b'"') {
+        chars.take_until(|b| {
+            is_delimiter_char(b, &LexerOptions::default()) || matches!(b, b':' | b'"' | b'{' | b'[')
+        });
+        return;
+    }
 
-                vec.push(JsonToken::Boolean(json_true));
+    // A broken string goes on until its closing quote, or the end of the line
+    // if it was never closed
+    *chars = start;
+    chars.next();
+    while let Some(b) = chars.next() {
+        match b {
+            b'\\' if chars.peek() != Some(b'\n') => {
+                chars.next();
             }
-            'n' => {
-                let null_len = 4;
-                let mut json_null = String::from(c);
-
-                let mut letter_count = 1;
-                while letter_count < null_len {
-                    if let Some(null_c) = chars.next() {
-                        json_null.push(null_c);
-                    } else {
-                        break;
-                    }
-                    letter_count += 1;
-                }
+            b'"' | b'\n' => break,
+            _ => {}
+        }
+    }
+}
+
+/// Lazily lexes the input one token at a time, stopping after the first error
+pub struct Lexer<'a> {
+    chars: Cursor<'a>,
+    options: LexerOptions,
+    failed: bool,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(raw: &'a str, options: &LexerOptions) -> Self {
+        Self {
+            chars: Cursor::new(raw),
+            options: options.clone(),
+            failed: false,
+        }
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<(JsonTokenRef<'a>, Span), LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let token = next_token(&mut self.chars, &self.options);
+        self.failed = token.is_err();
+        token.transpose()
+    }
+}
+
+/// Byte offset into the input along with the line and column found there
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Position {
+    pub(crate) offset: usize,
+    pub(crate) span: Span,
+}
+
+/// Token found by [`next_token_at`], with its span and the position right past it
+pub(crate) type TokenAt<'a> = (JsonTokenRef<'a>, Span, Position);
+
+/// Lexes the first token of `raw` found after `at`, returning it along with
+/// the position right past it so lexing can resume there later. Errors come
+/// with the offset lexing stopped at, which is the end of `raw` whenever more
+/// input could have made a difference
+pub(crate) fn next_token_at<'a>(
+    raw: &'a str,
+    at: Position,
+    options: &LexerOptions,
+) -> Result<Option<TokenAt<'a>>, (LexError, usize)> {
+    let mut chars = Cursor {
+        src: raw,
+        pos: at.offset,
+        line: at.span.line,
+        col: at.span.col,
+    };
+
+    let token = next_token(&mut chars, options).map_err(|err| (err, chars.pos))?;
+    let end = Position {
+        offset: chars.pos,
+        span: chars.span(),
+    };
+
+    Ok(token.map(|(token, span)| (token, span, end)))
+}
+
+/// Reads the next token, skipping any whitespace and comments before it
+fn next_token<'a>(
+    chars: &mut Cursor<'a>,
+    options: &LexerOptions,
+) -> Result<Option<(JsonTokenRef<'a>, Span)>, LexError> {
+    loop {
+        let span = chars.span();
+        let start = chars.pos;
+
+        let b = match chars.next() {
+            Some(b) => b,
+            None => return Ok(None),
+        };
 
-                vec.push(JsonToken::Null(json_null));
+        let token = match b {
+            b'{' => JsonTokenRef::OpenCurlyBracket,
+            b'}' => JsonTokenRef::CloseCurlyBracket,
+            b'[' => JsonTokenRef::OpenSquareBracket,
+            b']' => JsonTokenRef::CloseSquareBracket,
+            b':' => JsonTokenRef::Colon,
+            b',' => JsonTokenRef::Comma,
+            b'"' => JsonTokenRef::String(lex_string(chars, span, b'"', options)?),
+            b'\'' if options.allow_single_quotes => {
+                JsonTokenRef::String(lex_string(chars, span, b'\'', options)?)
             }
-            '-' | '.' | '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => {
-                let mut json_number = String::from(c);
-
-                let mut end_token: Option<JsonToken> = None;
-                while let Some(num_c) = chars.next() {
-                    if is_number_char(num_c) {
-                        json_number.push(num_c);
-                    } else if let Some(t) = check_end_of_token_value(num_c) {
-                        end_token = Some(t);
-                        break;
-                    } else {
-                        return Err(JsonTokenError::InvalidToken(num_c));
+            // Without non-finite numbers, `NaN` and `Infinity` are left to be keys
+            b'-' | b'I' | b'N'
+                if is_non_finite_at(chars, start)
+                    && (b == b'-'
+                        || options.allow_non_finite_numbers
+                        || !options.allow_unquoted_keys) =>
+            {
+                lex_non_finite(chars, start, options)?
+            }
+            b'A'..=b'Z' | b'a'..=b'z' | b'_' if options.allow_unquoted_keys => {
+                lex_identifier(chars, start, options)?
+            }
+            b'f' => {
+                lex_literal(chars, start, "false", options)?;
+                JsonTokenRef::False
+            }
+            b't' => {
+                lex_literal(chars, start, "true", options)?;
+                JsonTokenRef::True
+            }
+            b'n' => {
+                lex_literal(chars, start, "null", options)?;
+                JsonTokenRef::Null
+            }
+            b'-' | b'.' | b'0'..=b'9' => {
+                chars.take_until(|b| !is_number_char(b));
+                let json_number = chars.slice(start);
+
+                match chars.peek() {
+                    Some(num_b) if is_delimiter_char(num_b, options) => {}
+                    Some(b':') => {
+                        return Err(chars.error(JsonTokenError::UnexpectedColonAfterNumber(
+                            json_number.to_string(),
+                        )));
                     }
+                    Some(_) => {
+                        return Err(chars.error(JsonTokenError::InvalidToken(
+                            chars.peek_char().expect("peeked a byte"),
+                        )));
+                    }
+                    None => {}
                 }
 
-                vec.push(JsonToken::Number(json_number));
-                if let Some(t) = end_token {
-                    vec.push(t);
+                if let Err((err, at)) = validate_number(json_number) {
+                    return Err(LexError {
+                        error: JsonTokenError::InvalidNumber(json_number.to_string(), err),
+                        offset: start + at,
+                    });
                 }
+
+                JsonTokenRef::Number(json_number)
             }
-            ' ' | '\n' | '\t' => {
+            b'/' if options.allow_comments => {
+                skip_comment(chars)?;
+                continue;
+            }
+            b' ' | b'\n' | b'\r' | b'\t' => {
                 // Ignore them
+                continue;
             }
             _ => {
-                return Err(JsonTokenError::InvalidToken(c));
+                // Step over the whole character so the cursor stays on a boundary
+                chars.pos = start;
+                let c = chars.next_char().expect("consumed a byte");
+                return Err(LexError {
+                    error: JsonTokenError::InvalidToken(c),
+                    offset: start,
+                });
             }
         };
-    }
 
-    return Ok(vec);
+        return Ok(Some((token, span)));
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{lexer, JsonToken, JsonTokenError};
+    use std::borrow::Cow;
+
+    use super::{
+        lexer, lexer_borrowed, lexer_bytes, lexer_collect, lexer_with_options, strip_comments,
+        tokenize, JsonNumberError, JsonToken, JsonTokenError, JsonTokenRef, LexError, LexerOptions,
+        Span,
+    };
+
+    fn lex_tokens(raw: &str) -> Result<Vec<JsonToken>, LexError> {
+        let tokens = lexer(raw)?;
+        Ok(tokens.into_iter().map(|(token, _)| token).collect())
+    }
+
+    fn fails_at<T>(error: JsonTokenError, offset: usize) -> Result<T, LexError> {
+        Err(LexError { error, offset })
+    }
 
     #[test]
-    fn test_empty_input() -> Result<(), JsonTokenError> {
-        let input = "".to_string();
+    fn test_empty_input() -> Result<(), LexError> {
+        let input = "";
 
-        let tokens = lexer(input)?;
+        let tokens = lex_tokens(input)?;
         let expected = vec![];
 
         assert_eq!(tokens, expected);
@@ -167,10 +947,10 @@ mod tests {
     }
 
     #[test]
-    fn test_curly_bracket_tokens() -> Result<(), JsonTokenError> {
-        let input = "{}".to_string();
+    fn test_curly_bracket_tokens() -> Result<(), LexError> {
+        let input = "{}";
 
-        let tokens = lexer(input)?;
+        let tokens = lex_tokens(input)?;
         let expected = vec![JsonToken::OpenCurlyBracket, JsonToken::CloseCurlyBracket];
 
         assert_eq!(tokens, expected);
@@ -179,10 +959,10 @@ mod tests {
     }
 
     #[test]
-    fn test_square_bracket_tokens() -> Result<(), JsonTokenError> {
-        let input = "[]".to_string();
+    fn test_square_bracket_tokens() -> Result<(), LexError> {
+        let input = "[]";
 
-        let tokens = lexer(input)?;
+        let tokens = lex_tokens(input)?;
         let expected = vec![JsonToken::OpenSquareBracket, JsonToken::CloseSquareBracket];
 
         assert_eq!(tokens, expected);
@@ -191,10 +971,10 @@ mod tests {
     }
 
     #[test]
-    fn test_string_token() -> Result<(), JsonTokenError> {
-        let input = "\"name\"".to_string();
+    fn test_string_token() -> Result<(), LexError> {
+        let input = "\"name\"";
 
-        let tokens = lexer(input)?;
+        let tokens = lex_tokens(input)?;
         let expected = vec![JsonToken::String("name".into())];
 
         assert_eq!(tokens, expected);
@@ -204,16 +984,138 @@ mod tests {
 
     #[test]
     fn test_missing_string_token_end() {
-        let input = "\"name".to_string();
-        assert_eq!(lexer(input), Err(JsonTokenError::ExpectedEndOfString));
+        let input = "\"name";
+        assert_eq!(
+            lexer(input),
+            fails_at(
+                JsonTokenError::ExpectedEndOfString(Span { line: 1, col: 1 }),
+                5
+            )
+        );
+    }
+
+    #[test]
+    fn test_unterminated_string_position() {
+        let input = "{\n  \"a\": \"b\",\n  \"c\": \"unterminated\\n}";
+        let err = lexer(input).unwrap_err();
+
+        assert_eq!(
+            err.error,
+            JsonTokenError::ExpectedEndOfString(Span { line: 3, col: 8 })
+        );
+        assert_eq!(err.offset, input.len());
+        assert_eq!(
+            err.error.to_string(),
+            "Unterminated string starting at line 3, column 8"
+        );
+    }
+
+    #[test]
+    fn test_escaped_string_tokens() -> Result<(), LexError> {
+        let cases = [
+            (r#""\"""#, "\""),
+            (r#""\\""#, "\\"),
+            (r#""\/""#, "/"),
+            (r#""\b""#, "\u{0008}"),
+            (r#""\f""#, "\u{000C}"),
+            (r#""\n""#, "\n"),
+            (r#""\r""#, "\r"),
+            (r#""\t""#, "\t"),
+            (r#""she said \"hi\"""#, "she said \"hi\""),
+        ];
+
+        for (input, expected) in cases {
+            let tokens = lex_tokens(input)?;
+            assert_eq!(tokens, vec![JsonToken::String(expected.into())]);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_escaped_backslash_before_string_end() -> Result<(), LexError> {
+        let input = r#""foo\\""#;
+
+        let tokens = lex_tokens(input)?;
+        let expected = vec![JsonToken::String("foo\\".into())];
+
+        assert_eq!(tokens, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unterminated_escape() {
+        let input = "\"foo\\";
+        assert_eq!(
+            lexer(input),
+            fails_at(JsonTokenError::UnterminatedEscape, 5)
+        );
+    }
+
+    #[test]
+    fn test_invalid_escape() {
+        let input = r#""\x""#;
+        assert_eq!(
+            lexer(input),
+            fails_at(JsonTokenError::InvalidEscape('x'), 2)
+        );
+    }
+
+    #[test]
+    fn test_unicode_escape() -> Result<(), LexError> {
+        let input = r#""caf\u00e9""#;
+
+        let tokens = lex_tokens(input)?;
+        let expected = vec![JsonToken::String("café".into())];
+
+        assert_eq!(tokens, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_surrogate_pair_unicode_escape() -> Result<(), LexError> {
+        let input = r#""\uD83D\uDE00""#;
+
+        let tokens = lex_tokens(input)?;
+        let expected = vec![JsonToken::String("😀".into())];
+
+        assert_eq!(tokens, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_unicode_escape() {
+        let input = r#""\uZZZZ""#;
+        assert_eq!(
+            lexer(input),
+            fails_at(JsonTokenError::InvalidUnicodeEscape("ZZZZ".into()), 3)
+        );
+    }
+
+    #[test]
+    fn test_unpaired_surrogate_unicode_escape() {
+        let lone_high = r#""\uD83D""#;
+        assert_eq!(
+            lexer(lone_high),
+            fails_at(JsonTokenError::UnpairedSurrogate(0xD83D), 7)
+        );
+
+        let lone_low = r#""\uDE00""#;
+        assert_eq!(
+            lexer(lone_low),
+            fails_at(JsonTokenError::UnpairedSurrogate(0xDE00), 1)
+        );
     }
 
     #[test]
-    fn test_true_token() -> Result<(), JsonTokenError> {
-        let input = "true".to_string();
+    fn test_true_token() -> Result<(), LexError> {
+        let input = "true";
 
-        let tokens = lexer(input.to_owned())?;
-        let expected = vec![JsonToken::Boolean(input)];
+        let tokens = lex_tokens(input)?;
+        let expected = vec![JsonToken::True];
 
         assert_eq!(tokens, expected);
 
@@ -222,16 +1124,19 @@ mod tests {
 
     #[test]
     fn test_invalid_true_token() {
-        let input = "truea".to_string();
-        assert_eq!(lexer(input), Err(JsonTokenError::InvalidToken('a')));
+        let input = "truea";
+        assert_eq!(
+            lexer(input),
+            fails_at(JsonTokenError::InvalidLiteral("truea".into()), 4)
+        );
     }
 
     #[test]
-    fn test_false_token() -> Result<(), JsonTokenError> {
-        let input = "false".to_string();
+    fn test_false_token() -> Result<(), LexError> {
+        let input = "false";
 
-        let tokens = lexer(input)?;
-        let expected = vec![JsonToken::Boolean("false".into())];
+        let tokens = lex_tokens(input)?;
+        let expected = vec![JsonToken::False];
 
         assert_eq!(tokens, expected);
 
@@ -240,16 +1145,19 @@ mod tests {
 
     #[test]
     fn test_invalid_false_token() {
-        let input = "falseo".to_string();
-        assert_eq!(lexer(input), Err(JsonTokenError::InvalidToken('o')));
+        let input = "falseo";
+        assert_eq!(
+            lexer(input),
+            fails_at(JsonTokenError::InvalidLiteral("falseo".into()), 5)
+        );
     }
 
     #[test]
-    fn test_null_token() -> Result<(), JsonTokenError> {
-        let input = "null".to_string();
+    fn test_null_token() -> Result<(), LexError> {
+        let input = "null";
 
-        let tokens = lexer(input)?;
-        let expected = vec![JsonToken::Null("null".into())];
+        let tokens = lex_tokens(input)?;
+        let expected = vec![JsonToken::Null];
 
         assert_eq!(tokens, expected);
 
@@ -258,15 +1166,15 @@ mod tests {
 
     #[test]
     fn test_invalid_null_token() {
-        let input = "Null".to_string();
-        assert_eq!(lexer(input), Err(JsonTokenError::InvalidToken('N')));
+        let input = "Null";
+        assert_eq!(lexer(input), fails_at(JsonTokenError::InvalidToken('N'), 0));
     }
 
     #[test]
-    fn test_number_token() -> Result<(), JsonTokenError> {
-        let input = "360".to_string();
+    fn test_number_token() -> Result<(), LexError> {
+        let input = "360";
 
-        let tokens = lexer(input)?;
+        let tokens = lex_tokens(input)?;
         let expected = vec![JsonToken::Number("360".into())];
 
         assert_eq!(tokens, expected);
@@ -275,55 +1183,443 @@ mod tests {
     }
 
     #[test]
-    fn test_invalid_number_token() {
-        let input = "360f".to_string();
-        assert_eq!(lexer(input), Err(JsonTokenError::InvalidToken('f')));
+    fn test_exponent_number_tokens() -> Result<(), LexError> {
+        for input in ["1e10", "-2.5E-3", "0e0", "6.022e23"] {
+            let tokens = lex_tokens(input)?;
+            assert_eq!(tokens, vec![JsonToken::Number(input.into())]);
+        }
+
+        Ok(())
     }
 
     #[test]
-    fn test_comma_token() -> Result<(), JsonTokenError> {
-        let input = ",".to_string();
+    fn test_exponent_sign_and_leading_zeros() -> Result<(), LexError> {
+        for input in ["1E+5", "1e-0", "1e+0", "2E05", "-0.5e-007"] {
+            let tokens = lex_tokens(input)?;
+            assert_eq!(tokens, vec![JsonToken::Number(input.into())]);
+        }
 
-        let tokens = lexer(input)?;
-        let expected = vec![JsonToken::Comma];
-
-        assert_eq!(tokens, expected);
+        let json = crate::from_str("[1E+5, 1e-0, 2E05]").unwrap();
+        assert_eq!(json, json!([100000.0, 1.0, 200000.0]));
 
         Ok(())
     }
 
     #[test]
-    fn test_colon_token() -> Result<(), JsonTokenError> {
-        let input = ":".to_string();
+    fn test_plus_sign_outside_exponent() {
+        assert_eq!(lexer("+1"), fails_at(JsonTokenError::InvalidToken('+'), 0));
+        assert_eq!(
+            lexer("[+1]"),
+            fails_at(JsonTokenError::InvalidToken('+'), 1)
+        );
+        assert_eq!(
+            lexer("[-+1]"),
+            fails_at(
+                JsonTokenError::InvalidNumber(
+                    "-+1".into(),
+                    JsonNumberError::MissingDigitAfterMinus
+                ),
+                2
+            )
+        );
+        assert_eq!(
+            lexer("[1+5]"),
+            fails_at(
+                JsonTokenError::InvalidNumber("1+5".into(), JsonNumberError::UnexpectedChar('+')),
+                2
+            )
+        );
+        assert_eq!(
+            lexer("[1e+-5]"),
+            fails_at(
+                JsonTokenError::InvalidNumber(
+                    "1e+-5".into(),
+                    JsonNumberError::MissingExponentDigits
+                ),
+                4
+            )
+        );
+    }
 
-        let tokens = lexer(input)?;
-        let expected = vec![JsonToken::Colon];
+    #[test]
+    fn test_invalid_number_token() {
+        let input = "360f";
+        assert_eq!(lexer(input), fails_at(JsonTokenError::InvalidToken('f'), 3));
+    }
 
-        assert_eq!(tokens, expected);
+    #[test]
+    fn test_number_token_followed_by_whitespace() -> Result<(), LexError> {
+        let expected = vec![JsonToken::Number("20".into())];
+
+        assert_eq!(lex_tokens("20 ")?, expected);
+        assert_eq!(lex_tokens("20\n")?, expected);
+        assert_eq!(lex_tokens("20\t")?, expected);
 
         Ok(())
     }
 
     #[test]
-    fn test_json_tokens() -> Result<(), JsonTokenError> {
-        let input = "[{\"money\": null, \"age\": 20}, true, false]".to_string();
+    fn test_spaced_number_tokens() -> Result<(), LexError> {
+        let input = "[ 1 , 2 , 3 ]";
 
-        let tokens = lexer(input)?;
+        let tokens = lex_tokens(input)?;
         let expected = vec![
             JsonToken::OpenSquareBracket,
-            JsonToken::OpenCurlyBracket,
-            JsonToken::String("money".into()),
+            JsonToken::Number("1".into()),
+            JsonToken::Comma,
+            JsonToken::Number("2".into()),
+            JsonToken::Comma,
+            JsonToken::Number("3".into()),
+            JsonToken::CloseSquareBracket,
+        ];
+
+        assert_eq!(tokens, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_colon_after_number_token() {
+        let input = "{1: 2}";
+        assert_eq!(
+            lexer(input),
+            fails_at(JsonTokenError::UnexpectedColonAfterNumber("1".into()), 2)
+        );
+    }
+
+    #[test]
+    fn test_invalid_number_syntax() {
+        let cases = [
+            ("01", JsonNumberError::LeadingZero, 2),
+            ("-", JsonNumberError::MissingDigitAfterMinus, 2),
+            ("-.5", JsonNumberError::MissingDigitAfterMinus, 2),
+            (".5", JsonNumberError::MissingIntegerDigits, 1),
+            ("4-.5", JsonNumberError::UnexpectedChar('-'), 2),
+            ("1.", JsonNumberError::MissingFractionDigits, 3),
+            ("1.2.3", JsonNumberError::UnexpectedChar('.'), 4),
+            ("1e", JsonNumberError::MissingExponentDigits, 3),
+            ("1e+", JsonNumberError::MissingExponentDigits, 4),
+        ];
+
+        for (input, err, offset) in cases {
+            assert_eq!(
+                lexer(&format!("[{}]", input)),
+                fails_at(JsonTokenError::InvalidNumber(input.into(), err), offset)
+            );
+        }
+    }
+
+    #[test]
+    fn test_number_edge_cases() {
+        let cases = [
+            ("01", Err((JsonNumberError::LeadingZero, 2))),
+            ("-", Err((JsonNumberError::MissingDigitAfterMinus, 2))),
+            (".5", Err((JsonNumberError::MissingIntegerDigits, 1))),
+            ("5.", Err((JsonNumberError::MissingFractionDigits, 3))),
+            ("-0.0", Ok(())),
+            ("-0", Ok(())),
+            ("0", Ok(())),
+            ("-01", Err((JsonNumberError::LeadingZero, 3))),
+        ];
+
+        for (input, expected) in cases {
+            let result = lexer(&format!("[{}]", input));
+
+            match expected {
+                Ok(()) => assert_eq!(
+                    result.unwrap()[1].0,
+                    JsonToken::Number(input.into()),
+                    "{:?}",
+                    input
+                ),
+                Err((err, offset)) => assert_eq!(
+                    result,
+                    fails_at(JsonTokenError::InvalidNumber(input.into(), err), offset),
+                    "{:?}",
+                    input
+                ),
+            }
+        }
+
+        assert_eq!(
+            JsonNumberError::MissingDigitAfterMinus.to_string(),
+            "expected digit after minus sign"
+        );
+    }
+
+    #[test]
+    fn test_comma_token() -> Result<(), LexError> {
+        let input = ",";
+
+        let tokens = lex_tokens(input)?;
+        let expected = vec![JsonToken::Comma];
+
+        assert_eq!(tokens, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_colon_token() -> Result<(), LexError> {
+        let input = ":";
+
+        let tokens = lex_tokens(input)?;
+        let expected = vec![JsonToken::Colon];
+
+        assert_eq!(tokens, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_tokens() -> Result<(), LexError> {
+        let input = "[{\"money\": null, \"age\": 20}, true, false]";
+
+        let tokens = lex_tokens(input)?;
+        let expected = vec![
+            JsonToken::OpenSquareBracket,
+            JsonToken::OpenCurlyBracket,
+            JsonToken::String("money".into()),
             JsonToken::Colon,
-            JsonToken::Null("null".into()),
+            JsonToken::Null,
             JsonToken::Comma,
             JsonToken::String("age".into()),
             JsonToken::Colon,
             JsonToken::Number("20".into()),
             JsonToken::CloseCurlyBracket,
             JsonToken::Comma,
-            JsonToken::Boolean("true".into()),
+            JsonToken::True,
+            JsonToken::Comma,
+            JsonToken::False,
+            JsonToken::CloseSquareBracket,
+        ];
+
+        assert_eq!(tokens, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_token_spans() -> Result<(), LexError> {
+        let input = "{\n  \"a\": 1\n}";
+
+        let tokens = lexer(input)?;
+        let spans: Vec<Span> = tokens.iter().map(|(_, span)| *span).collect();
+
+        assert_eq!(tokens[1].0, JsonToken::String("a".into()));
+        assert_eq!(spans[1], Span { line: 2, col: 3 });
+
+        assert_eq!(
+            spans,
+            vec![
+                Span { line: 1, col: 1 },
+                Span { line: 2, col: 3 },
+                Span { line: 2, col: 6 },
+                Span { line: 2, col: 8 },
+                Span { line: 3, col: 1 },
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_comments_rejected_by_default() {
+        let input = "[1] // comment";
+        assert_eq!(lexer(input), fails_at(JsonTokenError::InvalidToken('/'), 4));
+    }
+
+    #[test]
+    fn test_line_comment_after_value() -> Result<(), LexError> {
+        let input = "{\"a\": 1// one\n, \"b\": \"//not a comment\" // two\n}";
+        let options = LexerOptions {
+            allow_comments: true,
+            ..Default::default()
+        };
+
+        let tokens: Vec<JsonToken> = lexer_with_options(input, &options)?
+            .into_iter()
+            .map(|(token, _)| token)
+            .collect();
+        let expected = vec![
+            JsonToken::OpenCurlyBracket,
+            JsonToken::String("a".into()),
+            JsonToken::Colon,
+            JsonToken::Number("1".into()),
+            JsonToken::Comma,
+            JsonToken::String("b".into()),
+            JsonToken::Colon,
+            JsonToken::String("//not a comment".into()),
+            JsonToken::CloseCurlyBracket,
+        ];
+
+        assert_eq!(tokens, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_block_comment_between_keys() -> Result<(), LexError> {
+        let input = "{\"a\": 1, /* the\n * b key **/ \"b\": 2}";
+        let options = LexerOptions {
+            allow_comments: true,
+            ..Default::default()
+        };
+
+        let tokens: Vec<JsonToken> = lexer_with_options(input, &options)?
+            .into_iter()
+            .map(|(token, _)| token)
+            .collect();
+        let expected = vec![
+            JsonToken::OpenCurlyBracket,
+            JsonToken::String("a".into()),
+            JsonToken::Colon,
+            JsonToken::Number("1".into()),
+            JsonToken::Comma,
+            JsonToken::String("b".into()),
+            JsonToken::Colon,
+            JsonToken::Number("2".into()),
+            JsonToken::CloseCurlyBracket,
+        ];
+
+        assert_eq!(tokens, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unterminated_block_comment() {
+        let input = "[1] /* comment *";
+        let options = LexerOptions {
+            allow_comments: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            lexer_with_options(input, &options),
+            fails_at(JsonTokenError::ExpectedEndOfComment, input.len())
+        );
+    }
+
+    #[test]
+    fn test_error_after_leading_comment() {
+        let options = LexerOptions {
+            allow_comments: true,
+            ..Default::default()
+        };
+
+        // Points at the bad token, not at the comment lexing started from
+        assert_eq!(
+            lexer_with_options("/* list */ [1, x]", &options),
+            fails_at(JsonTokenError::InvalidToken('x'), 15)
+        );
+        assert_eq!(
+            lexer_with_options("// list\n[\"\\q\"]", &options),
+            fails_at(JsonTokenError::InvalidEscape('q'), 11)
+        );
+    }
+
+    #[test]
+    fn test_leading_byte_order_mark() -> Result<(), LexError> {
+        let input = "\u{FEFF}{}";
+        assert_eq!(lexer(input)?, lexer("{}")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_byte_order_mark_mid_stream() {
+        let input = "[\u{FEFF}]";
+        assert_eq!(
+            lexer(input),
+            fails_at(JsonTokenError::InvalidToken('\u{FEFF}'), 1)
+        );
+    }
+
+    #[test]
+    fn test_carriage_return_whitespace() -> Result<(), LexError> {
+        let input = "{\r\n  \"a\": 1\r\n}";
+
+        let tokens = lex_tokens(input)?;
+        let expected = vec![
+            JsonToken::OpenCurlyBracket,
+            JsonToken::String("a".into()),
+            JsonToken::Colon,
+            JsonToken::Number("1".into()),
+            JsonToken::CloseCurlyBracket,
+        ];
+
+        assert_eq!(tokens, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_truncated_literals() {
+        for input in ["tru", "nul", "fals"] {
+            assert_eq!(
+                lexer(input),
+                fails_at(JsonTokenError::InvalidLiteral(input.into()), input.len())
+            );
+        }
+    }
+
+    #[test]
+    fn test_overlong_literals() {
+        for input in ["truex", "falsey", "nullx"] {
+            assert_eq!(
+                lexer(input),
+                fails_at(
+                    JsonTokenError::InvalidLiteral(input.into()),
+                    input.len() - 1
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_literal_followed_by_invalid_char() {
+        assert_eq!(
+            lexer("[true@]"),
+            fails_at(JsonTokenError::InvalidToken('@'), 5)
+        );
+        assert_eq!(
+            lexer("[tru]"),
+            fails_at(JsonTokenError::InvalidLiteral("tru".into()), 4)
+        );
+    }
+
+    #[test]
+    fn test_literals_followed_by_delimiters() -> Result<(), LexError> {
+        let tokens = lex_tokens("[true,false]\n{\"a\":null}")?;
+        let expected = vec![
+            JsonToken::OpenSquareBracket,
+            JsonToken::True,
+            JsonToken::Comma,
+            JsonToken::False,
+            JsonToken::CloseSquareBracket,
+            JsonToken::OpenCurlyBracket,
+            JsonToken::String("a".into()),
+            JsonToken::Colon,
+            JsonToken::Null,
+            JsonToken::CloseCurlyBracket,
+        ];
+
+        assert_eq!(tokens, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_multibyte_string_token() -> Result<(), LexError> {
+        let input = r#"["mañana ☕ 🦀", "ünï\"cödé"]"#;
+
+        let tokens = lex_tokens(input)?;
+        let expected = vec![
+            JsonToken::OpenSquareBracket,
+            JsonToken::String("mañana ☕ 🦀".into()),
             JsonToken::Comma,
-            JsonToken::Boolean("false".into()),
+            JsonToken::String("ünï\"cödé".into()),
             JsonToken::CloseSquareBracket,
         ];
 
@@ -331,4 +1627,489 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_spans_count_characters_not_bytes() -> Result<(), LexError> {
+        let tokens = lexer(r#"["☕🦀", 1]"#)?;
+        let spans: Vec<Span> = tokens.into_iter().map(|(_, span)| span).collect();
+
+        assert_eq!(spans[2], Span { line: 1, col: 6 });
+        assert_eq!(spans[3], Span { line: 1, col: 8 });
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_multibyte_tokens() {
+        assert_eq!(lexer("[ñ]"), fails_at(JsonTokenError::InvalidToken('ñ'), 1));
+        assert_eq!(
+            lexer("[1☕]"),
+            fails_at(JsonTokenError::InvalidToken('☕'), 2)
+        );
+        assert_eq!(
+            lexer(r#"["\é"]"#),
+            fails_at(JsonTokenError::InvalidEscape('é'), 3)
+        );
+    }
+
+    #[test]
+    fn test_borrowed_tokens_point_into_input() -> Result<(), LexError> {
+        let input = r#"{"name": "fulano", "age": 20}"#;
+        let range = input.as_bytes().as_ptr_range();
+
+        let tokens = lexer_borrowed(input, &LexerOptions::default())?;
+
+        let mut borrowed = 0;
+        for (token, _) in &tokens {
+            let text = match token {
+                JsonTokenRef::String(Cow::Borrowed(text)) => *text,
+                JsonTokenRef::Number(text) => *text,
+                JsonTokenRef::String(Cow::Owned(_)) => panic!("unescaped string was copied"),
+                _ => continue,
+            };
+            assert!(range.contains(&text.as_ptr()));
+            borrowed += 1;
+        }
+
+        assert_eq!(borrowed, 4);
+        assert_eq!(tokens[3].0, JsonTokenRef::String(Cow::Borrowed("fulano")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_borrowed_escaped_string_is_owned() -> Result<(), LexError> {
+        let input = r#"["tab\there", "\u00f1"]"#;
+
+        let tokens = lexer_borrowed(input, &LexerOptions::default())?;
+
+        assert!(matches!(
+            &tokens[1].0,
+            JsonTokenRef::String(Cow::Owned(text)) if text == "tab\there"
+        ));
+        assert!(matches!(
+            &tokens[3].0,
+            JsonTokenRef::String(Cow::Owned(text)) if text == "ñ"
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_collect_reports_every_error() {
+        let (tokens, errors) = lexer_collect("[1, x, \"a\",\n tru, 2]");
+
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    LexError {
+                        error: JsonTokenError::InvalidToken('x'),
+                        offset: 4
+                    },
+                    Span { line: 1, col: 5 }
+                ),
+                (
+                    LexError {
+                        error: JsonTokenError::InvalidLiteral("tru".into()),
+                        offset: 16
+                    },
+                    Span { line: 2, col: 2 }
+                ),
+            ]
+        );
+        assert_eq!(
+            tokens,
+            vec![
+                JsonToken::OpenSquareBracket,
+                JsonToken::Number("1".into()),
+                JsonToken::Comma,
+                JsonToken::Comma,
+                JsonToken::String("a".into()),
+                JsonToken::Comma,
+                JsonToken::Comma,
+                JsonToken::Number("2".into()),
+                JsonToken::CloseSquareBracket,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collect_skips_broken_strings() {
+        let (tokens, errors) = lexer_collect("{\"a\\q b\": 1, \"c\": \"open\n}");
+
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    LexError {
+                        error: JsonTokenError::InvalidEscape('q'),
+                        offset: 4
+                    },
+                    Span { line: 1, col: 2 }
+                ),
+                (
+                    LexError {
+                        error: JsonTokenError::ControlCharacterInString(
+                            '\n',
+                            Span { line: 1, col: 24 }
+                        ),
+                        offset: 23
+                    },
+                    Span { line: 1, col: 19 }
+                ),
+            ]
+        );
+        assert_eq!(tokens.first(), Some(&JsonToken::OpenCurlyBracket));
+        assert_eq!(tokens.last(), Some(&JsonToken::CloseCurlyBracket));
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let input = "[1, \"a\"]";
+        let expected: Vec<JsonToken> = lexer(input)
+            .unwrap()
+            .into_iter()
+            .map(|(token, _)| token)
+            .collect();
+
+        assert_eq!(tokenize(input), Ok(expected));
+        assert_eq!(
+            tokenize("[1, tru]"),
+            fails_at(JsonTokenError::InvalidLiteral("tru".into()), 7)
+        );
+    }
+
+    #[test]
+    fn test_lexer_bytes() {
+        let options = LexerOptions::default();
+
+        assert_eq!(
+            lexer_bytes(b"[1, true]", &options).unwrap(),
+            lexer("[1, true]").unwrap()
+        );
+        assert_eq!(
+            lexer_bytes(b"[1, \"a\\x\"]", &options),
+            fails_at(JsonTokenError::InvalidEscape('x'), 7)
+        );
+        // The byte-order mark still counts towards the offset
+        assert_eq!(
+            lexer_bytes("\u{FEFF}[tru]".as_bytes(), &options)
+                .unwrap_err()
+                .offset,
+            7
+        );
+    }
+
+    #[test]
+    fn test_lexer_bytes_truncated_utf8() {
+        // `☕` cut short after two of its three bytes
+        let err = lexer_bytes(b"[\"a\xE2\x98\"]", &LexerOptions::default()).unwrap_err();
+
+        assert_eq!(
+            err,
+            LexError {
+                error: JsonTokenError::InvalidUtf8,
+                offset: 3
+            }
+        );
+        assert_eq!(err.to_string(), "Invalid UTF-8 sequence (byte 3)");
+    }
+
+    #[test]
+    fn test_multibyte_regressions() {
+        // Used to slice the input in the middle of `☕` and panic
+        let (tokens, errors) = lexer_collect("[☕, 1]");
+        assert_eq!(tokens.len(), 4);
+        assert_eq!(
+            errors,
+            vec![(
+                LexError {
+                    error: JsonTokenError::InvalidToken('☕'),
+                    offset: 1
+                },
+                Span { line: 1, col: 2 }
+            )]
+        );
+
+        let options = LexerOptions {
+            allow_comments: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            lexer_bytes("[/é]".as_bytes(), &options),
+            fails_at(JsonTokenError::InvalidToken('/'), 1)
+        );
+        assert_eq!(
+            lexer_bytes("[\"\\uD800\\é\"]".as_bytes(), &options),
+            fails_at(JsonTokenError::UnpairedSurrogate(0xD800), 9)
+        );
+    }
+
+    #[test]
+    fn test_strip_comments() {
+        let input = "{\n  // name\n  \"a\": 1, /* inline */ \"b\": [2]\n}";
+
+        let stripped = strip_comments(input);
+        assert_eq!(
+            stripped,
+            "{\n         \n  \"a\": 1,              \"b\": [2]\n}"
+        );
+
+        let options = LexerOptions {
+            allow_comments: true,
+            ..Default::default()
+        };
+        assert_eq!(lexer(&stripped), lexer_with_options(input, &options));
+    }
+
+    #[test]
+    fn test_strip_comments_inside_strings() {
+        let input = r#"{"url": "http://a/*b*/", "q": "\"//\"", "c": 1} // done"#;
+
+        assert_eq!(
+            strip_comments(input),
+            r#"{"url": "http://a/*b*/", "q": "\"//\"", "c": 1}        "#
+        );
+    }
+
+    #[test]
+    fn test_strip_multiline_block_comment() {
+        let input = "[1, /* one\n  two ☕\r\n */ 2]";
+        let stripped = strip_comments(input);
+
+        assert_eq!(stripped, "[1,       \n       \r\n    2]");
+        assert_eq!(stripped.lines().count(), input.lines().count());
+
+        // Tokens after the comment keep the spans they have in the input
+        let tokens = lexer(&stripped).unwrap();
+        assert_eq!(
+            tokens[3],
+            (JsonToken::Number("2".into()), Span { line: 3, col: 5 })
+        );
+        assert_eq!(strip_comments("[1] /* open"), "[1]        ");
+    }
+
+    #[test]
+    fn test_collect_valid_input() {
+        let input = r#"{"a": [1, true, null]}"#;
+        let (tokens, errors) = lexer_collect(input);
+
+        assert!(errors.is_empty());
+        assert_eq!(tokens, lex_tokens(input).unwrap());
+    }
+
+    #[test]
+    fn test_single_quoted_strings() -> Result<(), LexError> {
+        let options = LexerOptions {
+            allow_single_quotes: true,
+            ..Default::default()
+        };
+
+        let tokens: Vec<JsonToken> = lexer_with_options("{'a': 'b'}", &options)?
+            .into_iter()
+            .map(|(token, _)| token)
+            .collect();
+        let expected = vec![
+            JsonToken::OpenCurlyBracket,
+            JsonToken::String("a".into()),
+            JsonToken::Colon,
+            JsonToken::String("b".into()),
+            JsonToken::CloseCurlyBracket,
+        ];
+        assert_eq!(tokens, expected);
+
+        assert_eq!(
+            lexer("{'a': 'b'}"),
+            fails_at(JsonTokenError::InvalidToken('\''), 1)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_quotes_inside_other_quotes() -> Result<(), LexError> {
+        let options = LexerOptions {
+            allow_single_quotes: true,
+            ..Default::default()
+        };
+        let input = r#"["it's", 'say "hi"', 'it\'s', "\u0027"]"#;
+
+        let strings: Vec<JsonToken> = lexer_with_options(input, &options)?
+            .into_iter()
+            .map(|(token, _)| token)
+            .filter(|token| matches!(token, JsonToken::String(_)))
+            .collect();
+        let expected = vec![
+            JsonToken::String("it's".into()),
+            JsonToken::String("say \"hi\"".into()),
+            JsonToken::String("it's".into()),
+            JsonToken::String("'".into()),
+        ];
+        assert_eq!(strings, expected);
+
+        // `\'` is only an escape where it's needed
+        assert_eq!(
+            lexer_with_options(r#"["it\'s"]"#, &options),
+            fails_at(JsonTokenError::InvalidEscape('\''), 5)
+        );
+        assert_eq!(lexer(r#"["it's"]"#).map(|tokens| tokens.len()), Ok(3));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unquoted_keys() -> Result<(), LexError> {
+        let options = LexerOptions {
+            allow_unquoted_keys: true,
+            ..Default::default()
+        };
+
+        let tokens: Vec<JsonToken> = lexer_with_options("{a: 1, _b2: true}", &options)?
+            .into_iter()
+            .map(|(token, _)| token)
+            .collect();
+        let expected = vec![
+            JsonToken::OpenCurlyBracket,
+            JsonToken::Identifier("a".into()),
+            JsonToken::Colon,
+            JsonToken::Number("1".into()),
+            JsonToken::Comma,
+            JsonToken::Identifier("_b2".into()),
+            JsonToken::Colon,
+            JsonToken::True,
+            JsonToken::CloseCurlyBracket,
+        ];
+        assert_eq!(tokens, expected);
+
+        assert_eq!(
+            lexer_with_options("{a-b: 1}", &options),
+            fails_at(JsonTokenError::InvalidToken('-'), 2)
+        );
+        assert_eq!(
+            lexer("{a: 1}"),
+            fails_at(JsonTokenError::InvalidToken('a'), 1)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_non_finite_words_as_unquoted_keys() -> Result<(), LexError> {
+        let options = LexerOptions {
+            allow_unquoted_keys: true,
+            ..Default::default()
+        };
+
+        let keys: Vec<JsonToken> = lexer_with_options("{NaN: 1, Infinity: 2}", &options)?
+            .into_iter()
+            .map(|(token, _)| token)
+            .filter(|token| matches!(token, JsonToken::Identifier(_)))
+            .collect();
+        let expected = vec![
+            JsonToken::Identifier("NaN".into()),
+            JsonToken::Identifier("Infinity".into()),
+        ];
+        assert_eq!(keys, expected);
+
+        assert_eq!(
+            lexer_with_options("[-Infinity]", &options),
+            fails_at(JsonTokenError::NonFiniteNumber("-Infinity".into()), 1)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_non_finite_numbers() -> Result<(), LexError> {
+        let options = LexerOptions {
+            allow_non_finite_numbers: true,
+            ..Default::default()
+        };
+
+        let tokens: Vec<JsonToken> = lexer_with_options("[NaN, Infinity, -Infinity]", &options)?
+            .into_iter()
+            .map(|(token, _)| token)
+            .filter(|token| matches!(token, JsonToken::Number(_)))
+            .collect();
+        let expected = vec![
+            JsonToken::Number("NaN".into()),
+            JsonToken::Number("Infinity".into()),
+            JsonToken::Number("-Infinity".into()),
+        ];
+        assert_eq!(tokens, expected);
+
+        assert_eq!(
+            lexer_with_options("[Infinityy]", &options),
+            fails_at(JsonTokenError::InvalidToken('I'), 1)
+        );
+        assert_eq!(
+            lexer_with_options("[-NaN]", &options),
+            fails_at(JsonTokenError::InvalidToken('N'), 2)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_non_finite_numbers_strict() {
+        for literal in ["NaN", "Infinity", "-Infinity"] {
+            assert_eq!(
+                lexer(&format!("[{}]", literal)),
+                fails_at(JsonTokenError::NonFiniteNumber(literal.into()), 1)
+            );
+        }
+    }
+
+    #[test]
+    fn test_control_character_in_string() {
+        let input = "{\"a\": \"line\nbreak\"}";
+        let err = lexer(input).unwrap_err();
+
+        assert_eq!(
+            err.error,
+            JsonTokenError::ControlCharacterInString('\n', Span { line: 1, col: 12 })
+        );
+        assert_eq!(err.offset, 11);
+        assert_eq!(
+            err.error.to_string(),
+            "Unescaped control character U+000A in string at line 1, column 12"
+        );
+        assert_eq!(
+            lex_tokens("\"tab\there\""),
+            fails_at(
+                JsonTokenError::ControlCharacterInString('\t', Span { line: 1, col: 5 }),
+                4
+            )
+        );
+
+        let options = LexerOptions {
+            allow_control_characters: true,
+            ..Default::default()
+        };
+        let tokens: Vec<JsonToken> = lexer_with_options(input, &options)
+            .unwrap()
+            .into_iter()
+            .map(|(token, _)| token)
+            .collect();
+        assert_eq!(tokens[3], JsonToken::String("line\nbreak".into()));
+    }
+
+    #[test]
+    fn test_escaped_control_character_in_string() -> Result<(), LexError> {
+        let input = r#""line\nbreak""#;
+
+        for options in [
+            LexerOptions::default(),
+            LexerOptions {
+                allow_control_characters: true,
+                ..Default::default()
+            },
+        ] {
+            let tokens = lexer_with_options(input, &options)?;
+            assert_eq!(tokens[0].0, JsonToken::String("line\nbreak".into()));
+        }
+
+        Ok(())
+    }
 }
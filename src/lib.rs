@@ -0,0 +1,110 @@
+#[macro_use]
+mod macros;
+
+mod balance;
+mod convert;
+mod diff;
+mod error;
+pub mod lexer;
+mod number;
+mod options;
+pub mod parser;
+mod patch;
+mod pointer;
+mod reader;
+mod recover;
+#[cfg(feature = "serde")]
+mod serde_impls;
+mod serializer;
+#[cfg(feature = "proptest")]
+mod strategy;
+mod stream;
+mod value;
+mod visitor;
+
+use std::str::FromStr;
+
+pub use balance::{check_balanced, BalanceError};
+pub use diff::{json_diff, Change, Difference};
+pub use error::JsonError;
+pub use lexer::{
+    lexer, lexer_borrowed, lexer_bytes, lexer_collect, lexer_with_options, strip_comments,
+    tokenize, JsonNumberError, JsonToken, JsonTokenError, JsonTokenRef, LexError, Lexer,
+    LexerOptions, Span,
+};
+pub use number::JsonNumber;
+pub use options::{ParseOptions, ParseOptionsBuilder};
+pub use parser::{
+    parser, parser_from_iter, parser_with_options, BracketContext, JsonObject, JsonParseError,
+    JsonValue, ParserOptions, DEFAULT_MAX_DEPTH,
+};
+pub use patch::PatchError;
+pub use pointer::PointerError;
+pub use reader::parse_reader;
+pub use recover::parse_recover;
+pub use serializer::{write_json, FormatOptions};
+#[cfg(feature = "proptest")]
+pub use strategy::{arb_json_number, arb_json_value};
+pub use stream::parse_stream;
+pub use value::JsonTypeError;
+pub use visitor::{parse_events, Visitor};
+
+/// Parses a JSON document into a [`JsonValue`] without printing anything
+///
+/// ```
+/// use crusty_json::{from_str, JsonValue};
+///
+/// let json = from_str(r#"{"a":1}"#).unwrap();
+///
+/// match &json {
+///     JsonValue::Object(obj) => assert_eq!(obj["a"], JsonValue::Number(1.into())),
+///     _ => unreachable!(),
+/// }
+/// ```
+pub fn from_str(input: &str) -> anyhow::Result<JsonValue> {
+    match input.parse() {
+        Ok(json) => Ok(json),
+        Err(JsonError::Token(err)) => Err(err.into()),
+        Err(JsonError::Parse(err)) => Err(err.into()),
+        Err(JsonError::Io(err)) => Err(err.into()),
+    }
+}
+
+/// ```
+/// use crusty_json::{JsonError, JsonValue};
+///
+/// let json: JsonValue = r#"[1, "two"]"#.parse().unwrap();
+/// assert_eq!(json[1], JsonValue::String("two".into()));
+///
+/// let err = "[1, two]".parse::<JsonValue>().unwrap_err();
+/// assert!(matches!(err, JsonError::Token(_)));
+/// ```
+impl FromStr for JsonValue {
+    type Err = JsonError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        parse_with_options(input, &ParseOptions::default())
+    }
+}
+
+/// Lexes and parses a document in one go, like [`str::parse`] but with
+/// lenient syntax or limits switched on through `options`
+pub fn parse_with_options(input: &str, options: &ParseOptions) -> Result<JsonValue, JsonError> {
+    let mut lex_error = None;
+
+    // Lex while parsing, setting aside the lexer error that cuts the token stream short
+    let tokens = Lexer::new(input, &options.lexer).map_while(|token| match token {
+        Ok((token, span)) => Some((token.into_owned(), span)),
+        Err(err) => {
+            lex_error = Some(err);
+            None
+        }
+    });
+    let json = parser_from_iter(tokens, &options.parser);
+
+    if let Some(err) = lex_error {
+        return Err(err.into());
+    }
+
+    Ok(json?)
+}
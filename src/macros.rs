@@ -0,0 +1,174 @@
+/// Builds a [`JsonValue`](crate::JsonValue) from JSON-like literal syntax. Any
+/// other Rust expression is converted through `JsonValue::from`
+///
+/// ```
+/// use crusty_json::{json, JsonValue};
+///
+/// let name = "fulano";
+/// let value = json!({
+///     "name": name,
+///     "roles": ["admin", null],
+///     "age": 10 * 2,
+/// });
+///
+/// assert_eq!(value["roles"][1], JsonValue::Null);
+/// assert_eq!(value["age"], JsonValue::Number(20.into()));
+/// ```
+#[macro_export]
+macro_rules! json {
+    (null) => {
+        $crate::JsonValue::Null
+    };
+    (true) => {
+        $crate::JsonValue::Boolean(true)
+    };
+    (false) => {
+        $crate::JsonValue::Boolean(false)
+    };
+    ([ $($tt:tt)* ]) => {
+        $crate::JsonValue::Array($crate::json_internal!(@array [] () $($tt)*))
+    };
+    ({}) => {
+        $crate::JsonValue::Object($crate::JsonObject::new())
+    };
+    ({ $($tt:tt)+ }) => {{
+        let mut object = $crate::JsonObject::new();
+        $crate::json_internal!(@object object $($tt)*);
+        $crate::JsonValue::Object(object)
+    }};
+    ($other:expr) => {
+        $crate::JsonValue::from($other)
+    };
+}
+
+/// Token muncher behind [`json!`], splitting array elements and object
+/// members on top-level commas
+#[macro_export]
+#[doc(hidden)]
+macro_rules! json_internal {
+    // Array elements are collected as expressions, the pending one in parentheses
+    (@array [$($elems:expr,)*] ()) => {
+        ::std::vec![$($elems,)*]
+    };
+    (@array [$($elems:expr,)*] ($($value:tt)+)) => {
+        ::std::vec![$($elems,)* $crate::json!($($value)+)]
+    };
+    (@array [$($elems:expr,)*] ($($value:tt)+) , $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json!($($value)+),] () $($rest)*)
+    };
+    (@array [$($elems:expr,)*] ($($value:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)*] ($($value)* $next) $($rest)*)
+    };
+
+    // Object members are inserted one at a time as soon as their value ends
+    (@object $object:ident) => {};
+    (@object $object:ident $key:tt : $($rest:tt)+) => {
+        $crate::json_internal!(@member $object [$key] () $($rest)+)
+    };
+    (@member $object:ident [$key:tt] ($($value:tt)+) , $($rest:tt)*) => {
+        $object.insert(($key).into(), $crate::json!($($value)+));
+        $crate::json_internal!(@object $object $($rest)*);
+    };
+    (@member $object:ident [$key:tt] ($($value:tt)+)) => {
+        $object.insert(($key).into(), $crate::json!($($value)+));
+    };
+    (@member $object:ident [$key:tt] ($($value:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::json_internal!(@member $object [$key] ($($value)* $next) $($rest)*)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::{JsonObject, JsonValue};
+
+    #[test]
+    fn test_scalars() {
+        assert_eq!(json!(null), JsonValue::Null);
+        assert_eq!(json!(true), JsonValue::Boolean(true));
+        assert_eq!(json!(false), JsonValue::Boolean(false));
+        assert_eq!(json!(-2.5), JsonValue::Number((-2.5).into()));
+        assert_eq!(json!("fulano"), JsonValue::String("fulano".into()));
+    }
+
+    #[test]
+    fn test_empty_containers() {
+        assert_eq!(json!([]), JsonValue::Array(vec![]));
+        assert_eq!(json!({}), JsonValue::Object(JsonObject::new()));
+    }
+
+    #[test]
+    fn test_array() {
+        let expected = JsonValue::Array(vec![
+            JsonValue::Number(1.into()),
+            JsonValue::Number(2.into()),
+            JsonValue::Number(3.into()),
+        ]);
+
+        assert_eq!(json!([1, 2, 3]), expected);
+        assert_eq!(json!([1, 2, 3,]), expected);
+    }
+
+    #[test]
+    fn test_nested() {
+        let mut level = JsonObject::new();
+        level.insert(
+            "level".into(),
+            JsonValue::Array(vec![JsonValue::Number(1.into()), JsonValue::Null]),
+        );
+
+        let mut expected = JsonObject::new();
+        expected.insert("name".into(), JsonValue::String("x".into()));
+        expected.insert(
+            "nums".into(),
+            JsonValue::Array(vec![
+                JsonValue::Number(1.into()),
+                JsonValue::Number(2.into()),
+                JsonValue::Number(3.into()),
+            ]),
+        );
+        expected.insert("ok".into(), JsonValue::Boolean(true));
+        expected.insert(
+            "roles".into(),
+            JsonValue::Array(vec![
+                JsonValue::String("admin".into()),
+                JsonValue::Object(level),
+                JsonValue::Array(vec![]),
+            ]),
+        );
+
+        let value = json!({
+            "name": "x",
+            "nums": [1, 2, 3],
+            "ok": true,
+            "roles": ["admin", {"level": [1, null]}, []]
+        });
+
+        assert_eq!(value, JsonValue::Object(expected));
+    }
+
+    #[test]
+    fn test_interpolated_expressions() {
+        let name = String::from("fulano");
+        let key = "age";
+        let nums = [1, 2];
+
+        let mut expected = JsonObject::new();
+        expected.insert("name".into(), JsonValue::String("fulano".into()));
+        expected.insert("age".into(), JsonValue::Number(21.into()));
+        expected.insert(
+            "sum".into(),
+            JsonValue::Array(vec![
+                JsonValue::Number(3.into()),
+                JsonValue::Number((-1).into()),
+            ]),
+        );
+
+        let value = json!({
+            "name": name,
+            key: 20 + 1,
+            "sum": [nums[0] + nums[1], nums[0] - nums[1]],
+        });
+
+        assert_eq!(value, JsonValue::Object(expected));
+    }
+}
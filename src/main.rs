@@ -1,74 +1,271 @@
-mod lexer;
-mod parser;
 mod utils;
 
-use clap::{ArgGroup, Parser};
-use nonblock::NonBlockingReader;
-use std::{fs, io, path::PathBuf};
-use utils::parse_json_and_print;
+use clap::{error::ErrorKind, ArgGroup, CommandFactory, Parser, ValueEnum};
+use crusty_json::JsonValue;
+use std::{
+    env,
+    fs::{self, File},
+    io::{self, BufWriter, IsTerminal, Read, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::Duration,
+};
+use utils::{parse_json_and_print, PrintOptions};
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ColorMode {
+    /// Color the output only when printing to a terminal
+    Auto,
+    /// Always color the output
+    Always,
+    /// Never color the output
+    Never,
+}
 
 /// Crusty JSON parser
 #[derive(Parser)]
-#[clap(group = ArgGroup::new("input").required(true).args(&["json", "file", "url"]))]
+#[clap(group = ArgGroup::new("input").args(&["json", "file", "url", "env"]))]
 struct Args {
     /// In-line json
-    #[clap(conflicts_with_all = ["file", "url"])]
+    #[clap(conflicts_with_all = ["file", "url", "env"])]
     json: Option<String>,
 
-    /// Path to load file
-    #[clap(short, long, conflicts_with_all = ["json", "url"])]
-    file: Option<PathBuf>,
+    /// Path to load file, can be given multiple times
+    #[clap(short, long, num_args = 1.., conflicts_with_all = ["json", "url", "env"])]
+    file: Vec<PathBuf>,
 
     /// URL to fetch
-    #[clap(short, long, conflicts_with_all = ["json", "file"])]
+    #[clap(short, long, conflicts_with_all = ["json", "file", "env"])]
     url: Option<String>,
+
+    /// Name of an environment variable holding the json
+    #[clap(long, value_name = "VARNAME", conflicts_with_all = ["json", "file", "url"])]
+    env: Option<String>,
+
+    /// Seconds to wait for the URL to respond before giving up
+    #[clap(long, requires = "url", default_value_t = 30)]
+    timeout: u64,
+
+    /// Largest response body to accept from the URL, in bytes
+    #[clap(long, requires = "url")]
+    max_bytes: Option<u64>,
+
+    /// Print indented JSON, the default except for queried values
+    #[clap(long, conflicts_with = "compact")]
+    pretty: bool,
+
+    /// Print JSON without any insignificant whitespace
+    #[clap(long)]
+    compact: bool,
+
+    /// Only check that the input is valid JSON, reporting the result through the exit code
+    #[clap(long, conflicts_with_all = ["pretty", "compact"])]
+    validate: bool,
+
+    /// JSON Pointer (like `/user/name`) selecting the value to print
+    #[clap(short, long)]
+    query: Option<String>,
+
+    /// When to highlight pretty-printed JSON
+    #[clap(long, value_enum, default_value_t = ColorMode::Auto)]
+    color: ColorMode,
+
+    /// Write the output to this file instead of stdout
+    #[clap(short, long)]
+    output: Option<PathBuf>,
+
+    /// Sort object keys at every level, for output that diffs cleanly
+    #[clap(long)]
+    sort_keys: bool,
+
+    /// Parse every line as its own document, reporting the lines that fail
+    #[clap(long)]
+    ndjson: bool,
+
+    /// Compare the input against this file, printing the differences and failing if there are any
+    #[clap(long, conflicts_with_all = ["pretty", "compact", "validate", "query", "ndjson"])]
+    diff: Option<PathBuf>,
+
+    /// Print node counts per type, depth and longest string instead of the JSON
+    #[clap(long, conflicts_with_all = ["pretty", "compact", "validate", "diff"])]
+    stats: bool,
+
+    /// Dump the tokens produced by the lexer before the JSON, for debugging
+    #[clap(long)]
+    tokens: bool,
+
+    /// Write non-ASCII characters as `\uXXXX` escapes, for pure ASCII output
+    #[clap(long)]
+    escape_non_ascii: bool,
 }
 
-fn cli() {
-    let args = Args::parse();
+fn parse_file_and_print(file_path: &Path, options: &PrintOptions, out: &mut impl Write) -> bool {
+    match fs::read_to_string(file_path) {
+        Ok(file_content) => parse_json_and_print(&file_content, options, out),
+        Err(err) => {
+            eprintln!("Error: {}: {}", file_path.display(), err);
+            false
+        }
+    }
+}
+
+fn load_json_file(file_path: &Path) -> anyhow::Result<JsonValue> {
+    let text = fs::read_to_string(file_path)?;
+    Ok(text.parse()?)
+}
+
+fn fetch_url(url: &str, timeout: u64, max_bytes: Option<u64>) -> anyhow::Result<String> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(timeout))
+        .build()?;
+    let res = client.get(url).send()?;
+
+    // Error pages are rarely JSON, so don't even try to parse them
+    if !res.status().is_success() {
+        anyhow::bail!("HTTP {} from {}", res.status(), url);
+    }
 
+    let Some(max_bytes) = max_bytes else {
+        return Ok(res.text()?);
+    };
+
+    if res.content_length().is_some_and(|len| len > max_bytes) {
+        anyhow::bail!("Response body is larger than {} bytes", max_bytes);
+    }
+
+    // The length header may be missing or lying, so stop reading right past the cap
+    let mut body = Vec::new();
+    res.take(max_bytes + 1).read_to_end(&mut body)?;
+    if body.len() as u64 > max_bytes {
+        anyhow::bail!("Response body is larger than {} bytes", max_bytes);
+    }
+
+    Ok(String::from_utf8(body)?)
+}
+
+fn cli(args: Args, options: &PrintOptions, out: &mut impl Write) -> bool {
     match args {
         Args {
             json: Some(text), ..
-        } => {
-            parse_json_and_print(text);
+        } => parse_json_and_print(&text, options, out),
+        Args { file: files, .. } if !files.is_empty() => {
+            let show_headers = files.len() > 1;
+
+            // Every file is reported even if a previous one failed
+            let mut success = true;
+            for file_path in files {
+                if show_headers {
+                    // A failed header write shows up again on the file's own output
+                    let _ = writeln!(out, "==> {} <==", file_path.display());
+                }
+                success &= parse_file_and_print(&file_path, options, out);
+            }
+
+            success
         }
         Args {
-            file: Some(file_path),
+            url: Some(url),
+            timeout,
+            max_bytes,
             ..
-        } => match fs::read_to_string(file_path) {
-            Ok(file_content) => parse_json_and_print(file_content),
-            Err(err) => eprintln!("{}", err),
+        } => match fetch_url(&url, timeout, max_bytes) {
+            Ok(text) => parse_json_and_print(&text, options, out),
+            Err(err) => {
+                eprintln!("Error: {}", err);
+                false
+            }
         },
-        Args { url: Some(url), .. } => match reqwest::blocking::get(url) {
-            Ok(res) => match res.text() {
-                Ok(text) => {
-                    parse_json_and_print(text);
-                }
-                Err(err) => eprintln!("{}", err),
-            },
-            Err(err) => eprintln!("{}", err),
+        Args {
+            env: Some(name), ..
+        } => match env::var(&name) {
+            Ok(text) => parse_json_and_print(&text, options, out),
+            Err(err) => {
+                eprintln!("Error: ${}: {}", name, err);
+                false
+            }
         },
-        _ => unreachable!(),
+        _ => {
+            // Stdin was empty, so one of the other input sources is required
+            Args::command()
+                .error(
+                    ErrorKind::MissingRequiredArgument,
+                    "expected in-line json, --file, --url, --env or piped stdin",
+                )
+                .exit();
+        }
     }
 }
 
-fn main() {
-    let stdin = io::stdin();
-    let mut nonblock_stdin = NonBlockingReader::from_fd(stdin).unwrap();
-
-    while !nonblock_stdin.is_eof() {
-        let mut buffer = String::new();
-        nonblock_stdin
-            .read_available_to_string(&mut buffer)
-            .unwrap();
-
-        if !buffer.is_empty() {
-            parse_json_and_print(buffer);
-            break;
-        } else {
-            cli();
-            break;
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let diff = match &args.diff {
+        Some(path) => match load_json_file(path) {
+            Ok(json) => Some(json),
+            Err(err) => {
+                eprintln!("Error: {}: {}", path.display(), err);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => None,
+    };
+
+    let options = PrintOptions {
+        pretty: args.pretty,
+        compact: args.compact,
+        validate: args.validate,
+        query: args.query.clone(),
+        color: match args.color {
+            ColorMode::Auto => args.output.is_none() && io::stdout().is_terminal(),
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+        },
+        sort_keys: args.sort_keys,
+        ndjson: args.ndjson,
+        diff,
+        stats: args.stats,
+        tokens: args.tokens,
+        escape_non_ascii: args.escape_non_ascii,
+    };
+
+    // Stdin is only read when there's no other input, and never from a terminal
+    let has_input =
+        args.json.is_some() || !args.file.is_empty() || args.url.is_some() || args.env.is_some();
+    let mut buffer = String::new();
+    if !has_input && !io::stdin().is_terminal() {
+        // Blocks until the writer closes the pipe, however slowly the data arrives
+        if let Err(err) = io::stdin().read_to_string(&mut buffer) {
+            eprintln!("Error: stdin: {}", err);
+            return ExitCode::FAILURE;
         }
     }
+
+    // Everything is buffered and flushed once at the end, which matters for big documents
+    let mut out: BufWriter<Box<dyn Write>> = BufWriter::new(match &args.output {
+        Some(path) => match File::create(path) {
+            Ok(file) => Box::new(file),
+            Err(err) => {
+                eprintln!("Error: {}: {}", path.display(), err);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => Box::new(io::stdout().lock()),
+    });
+
+    let mut success = if !buffer.is_empty() {
+        parse_json_and_print(&buffer, &options, &mut out)
+    } else {
+        cli(args, &options, &mut out)
+    };
+
+    if let Err(err) = out.flush() {
+        eprintln!("Error: {}", err);
+        success = false;
+    }
+
+    if success {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
 }
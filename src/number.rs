@@ -0,0 +1,347 @@
+#[cfg(feature = "rust_decimal")]
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+/// JSON number that remembers whether it was written as an integer
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonNumber {
+    /// Integer that fits in an `i64`
+    Int(i64),
+    /// Positive integer too large for an `i64`
+    UInt(u64),
+    /// Number with a fraction or exponent, or an integer too large for a `u64`
+    Float(f64),
+    /// Number exactly as written in the source, produced when
+    /// [`ParserOptions::preserve_number_text`](crate::ParserOptions) is set
+    Raw(String),
+    /// Exact decimal, produced for numbers with a fraction or exponent when
+    /// [`ParserOptions::parse_decimals`](crate::ParserOptions) is set
+    #[cfg(feature = "rust_decimal")]
+    Decimal(Decimal),
+}
+
+impl JsonNumber {
+    /// Picks the variant for an already validated number token
+    pub(crate) fn from_token(text: &str) -> Option<Self> {
+        let integral = !text.contains(['.', 'e', 'E']);
+
+        // `-0` only survives as a float
+        if integral && text != "-0" {
+            if let Ok(n) = text.parse::<i64>() {
+                return Some(JsonNumber::Int(n));
+            }
+            if let Ok(n) = text.parse::<u64>() {
+                return Some(JsonNumber::UInt(n));
+            }
+        }
+
+        // Literals like `1e400` overflow to infinity, which would be written
+        // back as `null`, so only the `Infinity` tokens may stand for one
+        text.parse::<f64>()
+            .ok()
+            .filter(|n| n.is_finite() || !text.ends_with(|c: char| c.is_ascii_digit()))
+            .map(JsonNumber::Float)
+    }
+
+    /// Like [`JsonNumber::from_token`], except fractions and exponents become
+    /// exact decimals whenever they fit in one
+    #[cfg(feature = "rust_decimal")]
+    pub(crate) fn from_token_decimal(text: &str) -> Option<Self> {
+        if !text.contains(['.', 'e', 'E']) {
+            return JsonNumber::from_token(text);
+        }
+
+        let decimal = if text.contains(['e', 'E']) {
+            Decimal::from_scientific(text)
+        } else {
+            Decimal::from_str_exact(text)
+        };
+
+        match decimal {
+            Ok(decimal) => Some(JsonNumber::Decimal(decimal)),
+            // Too large or too precise to be kept exactly
+            Err(_) => JsonNumber::from_token(text),
+        }
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        match *self {
+            JsonNumber::Int(n) => n as f64,
+            JsonNumber::UInt(n) => n as f64,
+            JsonNumber::Float(n) => n,
+            JsonNumber::Raw(ref text) => text.parse().unwrap_or(f64::NAN),
+            #[cfg(feature = "rust_decimal")]
+            JsonNumber::Decimal(n) => n.to_f64().unwrap_or(f64::NAN),
+        }
+    }
+
+    /// Returns the number if it is an integer within `i64` range
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            JsonNumber::Int(n) => Some(n),
+            JsonNumber::Raw(ref text) => JsonNumber::from_token(text)?.as_i64(),
+            _ => None,
+        }
+    }
+
+    /// Returns the number if it is a non-negative integer within `u64` range
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            JsonNumber::Int(n) => u64::try_from(n).ok(),
+            JsonNumber::UInt(n) => Some(n),
+            JsonNumber::Raw(ref text) => JsonNumber::from_token(text)?.as_u64(),
+            _ => None,
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        match self {
+            JsonNumber::Int(_) | JsonNumber::UInt(_) => true,
+            JsonNumber::Raw(text) => !text.contains(['.', 'e', 'E']),
+            _ => false,
+        }
+    }
+
+    /// Compares by numeric value rather than by variant, so `1` equals `1.0`
+    /// and, unlike `==`, `NaN` equals `NaN`
+    pub(crate) fn value_eq(&self, other: &JsonNumber) -> bool {
+        #[cfg(feature = "rust_decimal")]
+        if let (JsonNumber::Decimal(a), JsonNumber::Decimal(b)) = (self, other) {
+            return a == b;
+        }
+
+        let integer = |n: &JsonNumber| {
+            n.as_i64()
+                .map(i128::from)
+                .or_else(|| n.as_u64().map(i128::from))
+        };
+
+        // Integers beyond 2^53 would round when widened, so the float has to
+        // be whole and convert back to exactly the same integer
+        let whole = |n: i128, f: f64| f.fract() == 0.0 && f as i128 == n;
+
+        match (integer(self), integer(other)) {
+            (Some(a), Some(b)) => a == b,
+            (Some(n), None) => whole(n, other.as_f64()),
+            (None, Some(n)) => whole(n, self.as_f64()),
+            (None, None) => {
+                let (a, b) = (self.as_f64(), other.as_f64());
+                a == b || (a.is_nan() && b.is_nan())
+            }
+        }
+    }
+}
+
+impl fmt::Display for JsonNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            JsonNumber::Int(n) => write!(f, "{}", n),
+            JsonNumber::UInt(n) => write!(f, "{}", n),
+            // `Debug` keeps the `.0` on integral floats so they read back as floats
+            JsonNumber::Float(n) if n.is_finite() => write!(f, "{:?}", n),
+            // NaN and infinities have no JSON representation
+            JsonNumber::Float(_) => f.write_str("null"),
+            JsonNumber::Raw(ref text) => f.write_str(text),
+            #[cfg(feature = "rust_decimal")]
+            JsonNumber::Decimal(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+/// Hashes consistently with `==`, so each variant hashes on its own and `0.0`
+/// hashes like `-0.0`. Every `NaN` hashes the same even though no `NaN` is
+/// equal to another
+impl Hash for JsonNumber {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+
+        match *self {
+            JsonNumber::Int(n) => n.hash(state),
+            JsonNumber::UInt(n) => n.hash(state),
+            // Matches `-0.0` as well
+            JsonNumber::Float(0.0) => 0.0f64.to_bits().hash(state),
+            JsonNumber::Float(n) if n.is_nan() => f64::NAN.to_bits().hash(state),
+            JsonNumber::Float(n) => n.to_bits().hash(state),
+            JsonNumber::Raw(ref text) => text.hash(state),
+            #[cfg(feature = "rust_decimal")]
+            JsonNumber::Decimal(n) => n.hash(state),
+        }
+    }
+}
+
+impl From<i64> for JsonNumber {
+    fn from(n: i64) -> Self {
+        JsonNumber::Int(n)
+    }
+}
+
+impl From<i32> for JsonNumber {
+    fn from(n: i32) -> Self {
+        JsonNumber::Int(n.into())
+    }
+}
+
+impl From<u32> for JsonNumber {
+    fn from(n: u32) -> Self {
+        JsonNumber::Int(n.into())
+    }
+}
+
+impl From<u64> for JsonNumber {
+    fn from(n: u64) -> Self {
+        match i64::try_from(n) {
+            Ok(n) => JsonNumber::Int(n),
+            Err(_) => JsonNumber::UInt(n),
+        }
+    }
+}
+
+impl From<f64> for JsonNumber {
+    fn from(n: f64) -> Self {
+        JsonNumber::Float(n)
+    }
+}
+
+#[cfg(feature = "rust_decimal")]
+impl From<Decimal> for JsonNumber {
+    fn from(n: Decimal) -> Self {
+        JsonNumber::Decimal(n)
+    }
+}
+
+impl From<f32> for JsonNumber {
+    fn from(n: f32) -> Self {
+        JsonNumber::Float(n.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JsonNumber;
+
+    #[test]
+    fn test_integer_tokens() {
+        assert_eq!(JsonNumber::from_token("0"), Some(JsonNumber::Int(0)));
+        assert_eq!(JsonNumber::from_token("-20"), Some(JsonNumber::Int(-20)));
+        assert_eq!(
+            JsonNumber::from_token("9007199254740993"),
+            Some(JsonNumber::Int(9007199254740993))
+        );
+        assert_eq!(
+            JsonNumber::from_token("18446744073709551615"),
+            Some(JsonNumber::UInt(u64::MAX))
+        );
+        assert_eq!(
+            JsonNumber::from_token("18446744073709551616"),
+            Some(JsonNumber::Float(18446744073709551616.0))
+        );
+    }
+
+    #[test]
+    fn test_float_tokens() {
+        assert_eq!(JsonNumber::from_token("1.0"), Some(JsonNumber::Float(1.0)));
+        assert_eq!(
+            JsonNumber::from_token("1e2"),
+            Some(JsonNumber::Float(100.0))
+        );
+
+        assert_eq!(JsonNumber::from_token("1e400"), None);
+        assert_eq!(JsonNumber::from_token("-1.5e309"), None);
+        assert_eq!(
+            JsonNumber::from_token("-Infinity"),
+            Some(JsonNumber::Float(f64::NEG_INFINITY))
+        );
+
+        let negative_zero = JsonNumber::from_token("-0").unwrap();
+        assert_eq!(negative_zero, JsonNumber::Float(0.0));
+        assert!(negative_zero.as_f64().is_sign_negative());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            JsonNumber::Int(9007199254740993).to_string(),
+            "9007199254740993"
+        );
+        assert_eq!(
+            JsonNumber::UInt(u64::MAX).to_string(),
+            "18446744073709551615"
+        );
+        assert_eq!(JsonNumber::Float(1.0).to_string(), "1.0");
+        assert_eq!(JsonNumber::Float(-0.0).to_string(), "-0.0");
+        assert_eq!(JsonNumber::Float(-2.5e-3).to_string(), "-0.0025");
+        assert_eq!(JsonNumber::Float(f64::NAN).to_string(), "null");
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(JsonNumber::from(20u64), JsonNumber::Int(20));
+        assert_eq!(JsonNumber::from(u64::MAX), JsonNumber::UInt(u64::MAX));
+        assert_eq!(JsonNumber::Int(-1).as_u64(), None);
+        assert_eq!(JsonNumber::UInt(u64::MAX).as_i64(), None);
+        assert_eq!(JsonNumber::Float(1.0).as_i64(), None);
+        assert!(JsonNumber::Int(1).is_integer());
+        assert!(!JsonNumber::Float(1.0).is_integer());
+    }
+
+    #[test]
+    fn test_raw() {
+        let raw = JsonNumber::Raw("1e2".into());
+
+        assert_eq!(raw.to_string(), "1e2");
+        assert_eq!(raw.as_f64(), 100.0);
+        assert_eq!(raw.as_i64(), None);
+        assert!(!raw.is_integer());
+
+        let raw = JsonNumber::Raw("20".into());
+        assert_eq!(raw.as_i64(), Some(20));
+        assert_eq!(raw.as_u64(), Some(20));
+        assert!(raw.is_integer());
+    }
+
+    #[test]
+    fn test_value_eq() {
+        assert!(JsonNumber::Int(1).value_eq(&JsonNumber::Float(1.0)));
+        assert!(JsonNumber::Float(1.0).value_eq(&JsonNumber::Raw("1e0".into())));
+        assert!(
+            JsonNumber::UInt(u64::MAX).value_eq(&JsonNumber::Raw("18446744073709551615".into()))
+        );
+        assert!(JsonNumber::Float(f64::NAN).value_eq(&JsonNumber::Float(f64::NAN)));
+        assert!(JsonNumber::Float(0.0).value_eq(&JsonNumber::Float(-0.0)));
+
+        assert!(!JsonNumber::Int(1).value_eq(&JsonNumber::Float(1.5)));
+        assert!(!JsonNumber::Int(-1).value_eq(&JsonNumber::UInt(u64::MAX)));
+        // Both round to the same `f64`
+        assert!(!JsonNumber::Int(9007199254740993).value_eq(&JsonNumber::Float(9007199254740992.0)));
+        assert!(!JsonNumber::Int(0).value_eq(&JsonNumber::Float(f64::NAN)));
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    #[test]
+    fn test_decimal_tokens() {
+        use rust_decimal::Decimal;
+
+        assert_eq!(
+            JsonNumber::from_token_decimal("0.1"),
+            Some(JsonNumber::Decimal(Decimal::new(1, 1)))
+        );
+        assert_eq!(
+            JsonNumber::from_token_decimal("-2.5E-3"),
+            Some(JsonNumber::Decimal(Decimal::new(-25, 4)))
+        );
+        assert_eq!(
+            JsonNumber::from_token_decimal("20"),
+            Some(JsonNumber::Int(20))
+        );
+        assert_eq!(JsonNumber::from_token_decimal("1e400"), None);
+
+        let decimal = JsonNumber::from_token_decimal("19.99").unwrap();
+        assert_eq!(decimal.to_string(), "19.99");
+        assert_eq!(decimal.as_f64(), 19.99);
+        assert_eq!(decimal.as_i64(), None);
+        assert!(!decimal.is_integer());
+    }
+}
@@ -0,0 +1,250 @@
+use crate::{lexer::LexerOptions, parser::ParserOptions};
+
+/// Lexer and parser options in one place. The default is strict RFC 8259
+///
+/// ```
+/// use crusty_json::{parse_with_options, ParseOptions};
+///
+/// let options = ParseOptions::builder()
+///     .allow_comments(true)
+///     .max_depth(64)
+///     .build();
+///
+/// assert!(parse_with_options("[1 /* one */]", &options).is_ok());
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct ParseOptions {
+    pub lexer: LexerOptions,
+    pub parser: ParserOptions,
+}
+
+impl ParseOptions {
+    pub fn builder() -> ParseOptionsBuilder {
+        ParseOptionsBuilder::default()
+    }
+}
+
+impl AsRef<LexerOptions> for ParseOptions {
+    fn as_ref(&self) -> &LexerOptions {
+        &self.lexer
+    }
+}
+
+impl AsRef<ParserOptions> for ParseOptions {
+    fn as_ref(&self) -> &ParserOptions {
+        &self.parser
+    }
+}
+
+impl AsRef<LexerOptions> for LexerOptions {
+    fn as_ref(&self) -> &LexerOptions {
+        self
+    }
+}
+
+impl AsRef<ParserOptions> for ParserOptions {
+    fn as_ref(&self) -> &ParserOptions {
+        self
+    }
+}
+
+/// Builds [`ParseOptions`] one toggle at a time, starting from the strict defaults
+#[derive(Debug, Default, Clone)]
+pub struct ParseOptionsBuilder {
+    options: ParseOptions,
+}
+
+impl ParseOptionsBuilder {
+    /// See [`LexerOptions::allow_comments`]
+    pub fn allow_comments(mut self, allow: bool) -> Self {
+        self.options.lexer.allow_comments = allow;
+        self
+    }
+
+    /// See [`LexerOptions::allow_single_quotes`]
+    pub fn allow_single_quotes(mut self, allow: bool) -> Self {
+        self.options.lexer.allow_single_quotes = allow;
+        self
+    }
+
+    /// See [`LexerOptions::allow_unquoted_keys`]
+    pub fn allow_unquoted_keys(mut self, allow: bool) -> Self {
+        self.options.lexer.allow_unquoted_keys = allow;
+        self
+    }
+
+    /// See [`LexerOptions::allow_non_finite_numbers`]
+    pub fn allow_non_finite_numbers(mut self, allow: bool) -> Self {
+        self.options.lexer.allow_non_finite_numbers = allow;
+        self
+    }
+
+    /// See [`LexerOptions::allow_control_characters`]
+    pub fn allow_control_characters(mut self, allow: bool) -> Self {
+        self.options.lexer.allow_control_characters = allow;
+        self
+    }
+
+    /// See [`ParserOptions::reject_duplicate_keys`]
+    pub fn reject_duplicate_keys(mut self, reject: bool) -> Self {
+        self.options.parser.reject_duplicate_keys = reject;
+        self
+    }
+
+    /// See [`ParserOptions::allow_trailing_commas`]
+    pub fn allow_trailing_commas(mut self, allow: bool) -> Self {
+        self.options.parser.allow_trailing_commas = allow;
+        self
+    }
+
+    /// See [`ParserOptions::preserve_number_text`]
+    pub fn preserve_number_text(mut self, preserve: bool) -> Self {
+        self.options.parser.preserve_number_text = preserve;
+        self
+    }
+
+    /// See [`ParserOptions::parse_decimals`]
+    #[cfg(feature = "rust_decimal")]
+    pub fn parse_decimals(mut self, parse: bool) -> Self {
+        self.options.parser.parse_decimals = parse;
+        self
+    }
+
+    /// See [`ParserOptions::max_array_len`]
+    pub fn max_array_len(mut self, max: usize) -> Self {
+        self.options.parser.max_array_len = Some(max);
+        self
+    }
+
+    /// See [`ParserOptions::max_object_keys`]
+    pub fn max_object_keys(mut self, max: usize) -> Self {
+        self.options.parser.max_object_keys = Some(max);
+        self
+    }
+
+    /// See [`ParserOptions::max_depth`]
+    pub fn max_depth(mut self, max: usize) -> Self {
+        self.options.parser.max_depth = Some(max);
+        self
+    }
+
+    pub fn build(self) -> ParseOptions {
+        self.options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ParseOptions;
+    use crate::{
+        error::JsonError,
+        lexer::{lexer_with_options, JsonTokenError, LexError},
+        parse_with_options,
+        parser::{parser_with_options, JsonParseError},
+    };
+
+    #[test]
+    fn test_default_is_strict() {
+        for input in [
+            "[1 // one\n]",
+            "['a']",
+            "{a: 1}",
+            "[NaN]",
+            "[\"a\tb\"]",
+            "[1,]",
+        ] {
+            assert!(
+                parse_with_options(input, &ParseOptions::default()).is_err(),
+                "{:?} parsed",
+                input
+            );
+            assert!(input.parse::<crate::JsonValue>().is_err());
+        }
+    }
+
+    #[test]
+    fn test_lenient_toggles() {
+        let cases = [
+            (ParseOptions::builder().allow_comments(true), "[1 // one\n]"),
+            (ParseOptions::builder().allow_single_quotes(true), "['a']"),
+            (ParseOptions::builder().allow_unquoted_keys(true), "{a: 1}"),
+            (
+                ParseOptions::builder().allow_non_finite_numbers(true),
+                "[NaN]",
+            ),
+            (
+                ParseOptions::builder().allow_control_characters(true),
+                "[\"a\tb\"]",
+            ),
+            (ParseOptions::builder().allow_trailing_commas(true), "[1,]"),
+        ];
+
+        for (builder, input) in cases {
+            let options = builder.build();
+            assert!(parse_with_options(input, &options).is_ok(), "{:?}", input);
+
+            // Each half also works with the lexer and parser on their own
+            let tokens = lexer_with_options(input, &options).unwrap();
+            assert!(parser_with_options(&tokens, &options).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_restricting_toggles() {
+        let duplicate = r#"{"a": 1, "a": 2}"#;
+        assert!(parse_with_options(duplicate, &ParseOptions::default()).is_ok());
+        assert!(matches!(
+            parse_with_options(
+                duplicate,
+                &ParseOptions::builder().reject_duplicate_keys(true).build()
+            ),
+            Err(JsonError::Parse(JsonParseError::DuplicateKey(_, _)))
+        ));
+
+        let options = ParseOptions::builder()
+            .max_depth(2)
+            .max_array_len(2)
+            .max_object_keys(1)
+            .build();
+        assert!(parse_with_options(r#"[[1, 2], {"a": 3}]"#, &options).is_ok());
+        assert!(matches!(
+            parse_with_options("[[[]]]", &options),
+            Err(JsonError::Parse(JsonParseError::MaxDepthExceeded(2, _)))
+        ));
+        assert!(matches!(
+            parse_with_options("[1, 2, 3]", &options),
+            Err(JsonError::Parse(JsonParseError::TooManyElements(2, _)))
+        ));
+        assert!(matches!(
+            parse_with_options(r#"{"a": 1, "b": 2}"#, &options),
+            Err(JsonError::Parse(JsonParseError::TooManyElements(1, _)))
+        ));
+    }
+
+    #[test]
+    fn test_preserve_number_text() {
+        let options = ParseOptions::builder().preserve_number_text(true).build();
+
+        assert_eq!(
+            parse_with_options("[1.50]", &options).unwrap().to_string(),
+            "[1.50]"
+        );
+        assert_eq!(
+            parse_with_options("[1.50]", &ParseOptions::default())
+                .unwrap()
+                .to_string(),
+            "[1.5]"
+        );
+    }
+
+    #[test]
+    fn test_lexer_error_comes_first() {
+        assert_eq!(
+            parse_with_options("[1, tru", &ParseOptions::default()),
+            Err(JsonError::Token(LexError {
+                error: JsonTokenError::InvalidLiteral("tru".into()),
+                offset: 7
+            }))
+        );
+    }
+}
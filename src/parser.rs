@@ -1,251 +1,595 @@
-use crate::lexer::JsonToken;
-use std::collections::HashMap;
+use crate::{
+    lexer::{JsonToken, Span},
+    number::JsonNumber,
+};
+#[cfg(not(feature = "btreemap"))]
+use indexmap::IndexMap;
+#[cfg(feature = "btreemap")]
+use std::collections::BTreeMap;
+use std::{fmt, mem};
 use thiserror::Error;
 
-#[derive(Debug, PartialEq)]
+/// Object members, kept in the order they appear in the source
+#[cfg(not(feature = "btreemap"))]
+pub type JsonObject = IndexMap<String, JsonValue>;
+
+/// Object members, always sorted by key no matter the order in the source
+#[cfg(feature = "btreemap")]
+pub type JsonObject = BTreeMap<String, JsonValue>;
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum JsonValue {
     String(String),
-    Number(f64),
+    Number(JsonNumber),
     Boolean(bool),
     Null,
     Array(Vec<JsonValue>),
-    Object(HashMap<String, JsonValue>),
+    Object(JsonObject),
 }
 
 #[derive(Error, Debug, PartialEq)]
 pub enum JsonParseError {
     #[error("No tokens to parse from")]
     NoTokens,
-    #[error("Expected object or array as root, got `{0:?}`")]
-    ExpectedObjectOrArrayAsRoot(JsonToken),
-    #[error("Expected end-of-object")]
-    ExpectedEndOfObject,
-    #[error("Expected end-of-array")]
-    ExpectedEndOfArray,
-    #[error("Expected object key, got `{0:?}`")]
-    ExpectedObjectKey(JsonToken),
-    #[error("Expected colon after key, got `{0:?}`")]
-    ExpectedColonAfterKey(Option<JsonToken>),
-    #[error("Expected comma or end-of-object, got `{0:?}`")]
-    ExpectedCommaOrEndOfObject(Option<JsonToken>),
-    #[error("Expected comma or end-of-array, got `{0:?}`")]
-    ExpectedCommaOrEndOfArray(Option<JsonToken>),
-    #[error("Invalid json value, got `{0:?}`")]
-    InvalidValue(Option<JsonToken>),
-    #[error("Invalid json number, got `{0}`")]
-    InvalidNumberValue(String),
-    #[error("Invalid json boolean, got `{0}`")]
-    InvalidBooleanValue(String),
-    #[error("Invalid json null, got `{0}`")]
-    InvalidNullValue(String),
-    #[error("Trailing comma")]
-    TrailingComma,
+    #[error("Expected object or array as root, got `{0:?}` at {1}")]
+    ExpectedObjectOrArrayAsRoot(JsonToken, Span),
+    #[error("Expected end-of-object at {0}")]
+    ExpectedEndOfObject(Span),
+    #[error("Expected end-of-array at {0}")]
+    ExpectedEndOfArray(Span),
+    #[error("Expected object key, got `{0:?}` at {1}")]
+    ExpectedObjectKey(JsonToken, Span),
+    #[error("Expected colon after key, got `{0:?}` at {1}")]
+    ExpectedColonAfterKey(Option<JsonToken>, Span),
+    #[error("Expected comma or end-of-object, got `{0:?}` at {1}")]
+    ExpectedCommaOrEndOfObject(Option<JsonToken>, Span),
+    #[error("Expected comma or end-of-array, got `{0:?}` at {1}")]
+    ExpectedCommaOrEndOfArray(Option<JsonToken>, Span),
+    #[error("Invalid json value, got `{0:?}` at {1}")]
+    InvalidValue(Option<JsonToken>, Span),
+    #[error("Invalid json number, got `{0}` at {1}")]
+    InvalidNumberValue(String, Span),
+    #[error("Trailing comma at {0}")]
+    TrailingComma(Span),
+    #[error("Unexpected content after root value, got `{0:?}` at {1}")]
+    TrailingTokens(JsonToken, Span),
+    #[error("Duplicate object key `{0}` at {1}")]
+    DuplicateKey(String, Span),
+    #[error("Unexpected closing bracket `{0}` {1} at {2}")]
+    UnexpectedClosingBracket(char, BracketContext, Span),
+    #[error("Too many elements, no more than {0} are allowed, at {1}")]
+    TooManyElements(usize, Span),
+    #[error("Containers nested deeper than {0} levels at {1}")]
+    MaxDepthExceeded(usize, Span),
 }
 
-fn parse_value(
-    token: Option<&JsonToken>,
-    iter: &mut dyn Iterator<Item = &JsonToken>,
-) -> Result<JsonValue, JsonParseError> {
-    let value_token = match token {
-        Some(v) => v,
-        None => iter.next().ok_or(JsonParseError::InvalidValue(None))?,
-    };
+/// Where a closing bracket that doesn't match the innermost open one was found
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BracketContext {
+    Root,
+    Object,
+    Array,
+}
+
+impl BracketContext {
+    /// Fails if `token` is a closing bracket that doesn't close this context
+    pub(crate) fn check(self, token: &JsonToken, span: Span) -> Result<(), JsonParseError> {
+        let bracket = match token {
+            JsonToken::CloseCurlyBracket => '}',
+            JsonToken::CloseSquareBracket => ']',
+            _ => return Ok(()),
+        };
 
-    match value_token {
-        JsonToken::String(json_string) => {
-            return Ok(JsonValue::String(json_string.to_string()));
+        match (self, bracket) {
+            (BracketContext::Object, '}') | (BracketContext::Array, ']') => Ok(()),
+            _ => Err(JsonParseError::UnexpectedClosingBracket(
+                bracket, self, span,
+            )),
         }
-        JsonToken::Number(json_number) => match json_number.parse::<f64>() {
-            Ok(number) => {
-                return Ok(JsonValue::Number(number));
-            }
-            Err(_) => {
-                return Err(JsonParseError::InvalidNumberValue(json_number.to_string()));
+    }
+}
+
+impl fmt::Display for BracketContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BracketContext::Root => f.write_str("outside of any object or array"),
+            BracketContext::Object => f.write_str("inside an object"),
+            BracketContext::Array => f.write_str("inside an array"),
+        }
+    }
+}
+
+/// Nesting [`ParserOptions::max_depth`] allows by default. Comparing or
+/// printing a value recurses into it, so values nested much deeper than this
+/// would overflow the stack long after parsing them went fine
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+#[derive(Debug, Clone)]
+pub struct ParserOptions {
+    /// Fail on the first repeated key within an object instead of keeping the last value
+    pub reject_duplicate_keys: bool,
+    /// Accept a single comma right before `]` or `}`
+    pub allow_trailing_commas: bool,
+    /// Keep numbers as [`JsonNumber::Raw`] so they serialize exactly as written
+    pub preserve_number_text: bool,
+    /// Keep numbers with a fraction or exponent as exact [`JsonNumber::Decimal`]s
+    /// instead of `f64`s, falling back to `f64` for those that don't fit
+    #[cfg(feature = "rust_decimal")]
+    pub parse_decimals: bool,
+    /// Fail as soon as an array would get more elements than this
+    pub max_array_len: Option<usize>,
+    /// Fail as soon as an object would get more keys than this
+    pub max_object_keys: Option<usize>,
+    /// Fail as soon as containers nest deeper than this, where `[[]]` counts as 2.
+    /// Defaults to [`DEFAULT_MAX_DEPTH`], `None` lifts the limit altogether
+    pub max_depth: Option<usize>,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            reject_duplicate_keys: false,
+            allow_trailing_commas: false,
+            preserve_number_text: false,
+            #[cfg(feature = "rust_decimal")]
+            parse_decimals: false,
+            max_array_len: None,
+            max_object_keys: None,
+            max_depth: Some(DEFAULT_MAX_DEPTH),
+        }
+    }
+}
+
+/// Token the parser can either take apart or copy out of, so tokens borrowed
+/// from a slice are only cloned into the values and errors that need them
+trait ParserToken: Sized {
+    fn token(&self) -> &JsonToken;
+    fn into_token(self) -> JsonToken;
+    /// Gives back the payload of a `String` token, or the token itself otherwise
+    fn into_string(self) -> Result<String, Self>;
+}
+
+impl ParserToken for JsonToken {
+    fn token(&self) -> &JsonToken {
+        self
+    }
+
+    fn into_token(self) -> JsonToken {
+        self
+    }
+
+    fn into_string(self) -> Result<String, Self> {
+        match self {
+            JsonToken::String(json_string) => Ok(json_string),
+            token => Err(token),
+        }
+    }
+}
+
+impl ParserToken for &JsonToken {
+    fn token(&self) -> &JsonToken {
+        self
+    }
+
+    fn into_token(self) -> JsonToken {
+        self.clone()
+    }
+
+    fn into_string(self) -> Result<String, Self> {
+        match self {
+            JsonToken::String(json_string) => Ok(json_string.clone()),
+            token => Err(token),
+        }
+    }
+}
+
+/// Token iterator that remembers the span of the last token it yielded, so
+/// errors found at the end of the input can still point somewhere useful
+struct Tokens<I> {
+    iter: I,
+    span: Span,
+}
+
+impl<T: ParserToken, I: Iterator<Item = (T, Span)>> Tokens<I> {
+    fn new(tokens: I) -> Self {
+        Self {
+            iter: tokens,
+            span: Span { line: 1, col: 1 },
+        }
+    }
+
+    fn next(&mut self) -> Option<T> {
+        let (token, span) = self.iter.next()?;
+        self.span = span;
+        Some(token)
+    }
+}
+
+/// Container whose members are still being parsed
+enum Frame {
+    Array(Vec<JsonValue>),
+    /// Object along with the key, and its span, of the member being parsed
+    Object(JsonObject, String, Span),
+}
+
+impl Frame {
+    fn into_value(self) -> JsonValue {
+        match self {
+            Frame::Array(arr) => JsonValue::Array(arr),
+            Frame::Object(obj, _, _) => JsonValue::Object(obj),
+        }
+    }
+}
+
+/// Outcome of reading the start of a value
+enum Opened<T> {
+    /// A scalar or empty container, already complete
+    Value(JsonValue),
+    /// An array along with the first token of its first element
+    Array(T),
+    /// An object along with the key, and its span, of its first member
+    Object(String, Span),
+}
+
+/// Reads what follows `[` or a comma inside an array, giving back the first
+/// token of the next element, or `None` if the array ends there
+fn next_array_member<T: ParserToken, I: Iterator<Item = (T, Span)>>(
+    iter: &mut Tokens<I>,
+    comma: Option<Span>,
+    options: &ParserOptions,
+) -> Result<Option<T>, JsonParseError> {
+    match iter.next() {
+        Some(token) if matches!(token.token(), JsonToken::CloseSquareBracket) => match comma {
+            Some(comma_span) if !options.allow_trailing_commas => {
+                Err(JsonParseError::TrailingComma(comma_span))
             }
+            _ => Ok(None),
         },
-        JsonToken::Boolean(json_boolean) => match json_boolean.as_str() {
-            "true" => {
-                return Ok(JsonValue::Boolean(true));
-            }
-            "false" => {
-                return Ok(JsonValue::Boolean(false));
+        Some(token) => Ok(Some(token)),
+        None => Err(JsonParseError::ExpectedEndOfArray(iter.span)),
+    }
+}
+
+/// Reads what follows `{` or a comma inside an object up to the colon, giving
+/// back the key of the next member, or `None` if the object ends there
+fn next_object_member<T: ParserToken, I: Iterator<Item = (T, Span)>>(
+    iter: &mut Tokens<I>,
+    comma: Option<Span>,
+    options: &ParserOptions,
+) -> Result<Option<(String, Span)>, JsonParseError> {
+    let token = iter
+        .next()
+        .ok_or(JsonParseError::ExpectedEndOfObject(iter.span))?;
+
+    if let JsonToken::CloseCurlyBracket = token.token() {
+        return match comma {
+            Some(comma_span) if !options.allow_trailing_commas => {
+                Err(JsonParseError::TrailingComma(comma_span))
             }
+            _ => Ok(None),
+        };
+    }
+
+    let key_span = iter.span;
+    let key = match token.into_string() {
+        Ok(json_string) => json_string,
+        // The lexer only lets bare identifiers through in lenient mode
+        Err(token) => match token.token() {
+            JsonToken::Identifier(name) => name.clone(),
             _ => {
-                return Err(JsonParseError::InvalidBooleanValue(
-                    json_boolean.to_string(),
+                BracketContext::Object.check(token.token(), iter.span)?;
+                return Err(JsonParseError::ExpectedObjectKey(
+                    token.into_token(),
+                    iter.span,
                 ));
             }
         },
-        JsonToken::Null(json_null) => match json_null.as_str() {
-            "null" => {
-                return Ok(JsonValue::Null);
+    };
+
+    match iter.next() {
+        Some(t) if matches!(t.token(), JsonToken::Colon) => Ok(Some((key, key_span))),
+        Some(t) => {
+            BracketContext::Object.check(t.token(), iter.span)?;
+            Err(JsonParseError::ExpectedColonAfterKey(
+                Some(t.into_token()),
+                iter.span,
+            ))
+        }
+        None => Err(JsonParseError::ExpectedColonAfterKey(None, iter.span)),
+    }
+}
+
+/// Reads the start of a value, going no further than the first member of a
+/// container. `depth` is how many containers the value is nested in
+fn open_value<T: ParserToken, I: Iterator<Item = (T, Span)>>(
+    token: Option<T>,
+    context: BracketContext,
+    depth: usize,
+    iter: &mut Tokens<I>,
+    options: &ParserOptions,
+) -> Result<Opened<T>, JsonParseError> {
+    let value_token = match token {
+        Some(v) => v,
+        None => iter
+            .next()
+            .ok_or(JsonParseError::InvalidValue(None, iter.span))?,
+    };
+
+    let value_token = match value_token.into_string() {
+        Ok(json_string) => return Ok(Opened::Value(JsonValue::String(json_string))),
+        Err(token) => token,
+    };
+
+    let value = match value_token.token() {
+        JsonToken::Number(json_number) => match JsonNumber::from_token(json_number) {
+            Some(_) if options.preserve_number_text => {
+                JsonValue::Number(JsonNumber::Raw(json_number.clone()))
             }
-            _ => {
-                return Err(JsonParseError::InvalidNullValue(json_null.to_string()));
+            #[cfg(feature = "rust_decimal")]
+            Some(number) if options.parse_decimals => {
+                JsonValue::Number(JsonNumber::from_token_decimal(json_number).unwrap_or(number))
+            }
+            Some(number) => JsonValue::Number(number),
+            None => {
+                return Err(JsonParseError::InvalidNumberValue(
+                    json_number.clone(),
+                    iter.span,
+                ))
             }
         },
+        JsonToken::True => JsonValue::Boolean(true),
+        JsonToken::False => JsonValue::Boolean(false),
+        JsonToken::Null => JsonValue::Null,
+        JsonToken::OpenCurlyBracket | JsonToken::OpenSquareBracket
+            if options.max_depth.is_some_and(|max| depth >= max) =>
+        {
+            return Err(JsonParseError::MaxDepthExceeded(depth, iter.span));
+        }
         JsonToken::OpenCurlyBracket => {
-            return Ok(parse_object(iter)?);
+            return Ok(match next_object_member(iter, None, options)? {
+                Some((key, key_span)) => Opened::Object(key, key_span),
+                None => Opened::Value(JsonValue::Object(JsonObject::new())),
+            })
         }
         JsonToken::OpenSquareBracket => {
-            return Ok(parse_array(iter)?);
+            return Ok(match next_array_member(iter, None, options)? {
+                Some(first) => Opened::Array(first),
+                None => Opened::Value(JsonValue::Array(Vec::new())),
+            })
         }
-        _ => {
-            return Err(JsonParseError::InvalidValue(Some(value_token.to_owned())));
+        token => {
+            context.check(token, iter.span)?;
+            return Err(JsonParseError::InvalidValue(
+                Some(value_token.into_token()),
+                iter.span,
+            ));
         }
     };
-}
-
-fn parse_object(iter: &mut dyn Iterator<Item = &JsonToken>) -> Result<JsonValue, JsonParseError> {
-    let mut obj: HashMap<String, JsonValue> = HashMap::new();
 
-    let mut done = false;
-    let mut comma_after_value = false;
+    Ok(Opened::Value(value))
+}
 
-    while let Some(token) = iter.next() {
-        if let JsonToken::CloseCurlyBracket = token {
-            if comma_after_value {
-                return Err(JsonParseError::TrailingComma);
-            } else {
-                done = true;
-                break;
+/// Reads what follows a value inside an array, giving back the first token of
+/// the next element, or `None` if the array ends there
+fn after_array_value<T: ParserToken, I: Iterator<Item = (T, Span)>>(
+    iter: &mut Tokens<I>,
+    options: &ParserOptions,
+) -> Result<Option<T>, JsonParseError> {
+    match iter.next() {
+        Some(t) => match t.token() {
+            JsonToken::Comma => {
+                let comma_span = iter.span;
+                next_array_member(iter, Some(comma_span), options)
             }
-        }
+            JsonToken::CloseSquareBracket => Ok(None),
+            token => {
+                BracketContext::Array.check(token, iter.span)?;
+                Err(JsonParseError::ExpectedCommaOrEndOfArray(
+                    Some(t.into_token()),
+                    iter.span,
+                ))
+            }
+        },
+        None => Err(JsonParseError::ExpectedCommaOrEndOfArray(None, iter.span)),
+    }
+}
 
-        let key = match token {
-            JsonToken::String(json_string) => json_string.to_string(),
-            _ => {
-                return Err(JsonParseError::ExpectedObjectKey(token.to_owned()));
+/// Reads what follows a value inside an object, giving back the key of the
+/// next member, or `None` if the object ends there
+fn after_object_value<T: ParserToken, I: Iterator<Item = (T, Span)>>(
+    iter: &mut Tokens<I>,
+    options: &ParserOptions,
+) -> Result<Option<(String, Span)>, JsonParseError> {
+    match iter.next() {
+        Some(t) => match t.token() {
+            JsonToken::Comma => {
+                let comma_span = iter.span;
+                next_object_member(iter, Some(comma_span), options)
             }
-        };
+            JsonToken::CloseCurlyBracket => Ok(None),
+            token => {
+                BracketContext::Object.check(token, iter.span)?;
+                Err(JsonParseError::ExpectedCommaOrEndOfObject(
+                    Some(t.into_token()),
+                    iter.span,
+                ))
+            }
+        },
+        None => Err(JsonParseError::ExpectedCommaOrEndOfObject(None, iter.span)),
+    }
+}
 
-        match iter.next() {
-            Some(t) => {
-                if let JsonToken::Colon = t {
-                    // Do nothing
-                } else {
-                    return Err(JsonParseError::ExpectedColonAfterKey(Some(t.to_owned())));
-                }
+/// Fails if a container already holding `len` members can't take another one
+fn check_len(len: usize, max: Option<usize>, span: Span) -> Result<(), JsonParseError> {
+    match max {
+        Some(max) if len >= max => Err(JsonParseError::TooManyElements(max, span)),
+        _ => Ok(()),
+    }
+}
+
+/// Parses a whole value. Open containers are kept on a stack of their own
+/// rather than the call stack, so no amount of nesting can overflow it
+fn parse_value<T: ParserToken, I: Iterator<Item = (T, Span)>>(
+    mut token: Option<T>,
+    mut context: BracketContext,
+    iter: &mut Tokens<I>,
+    options: &ParserOptions,
+) -> Result<JsonValue, JsonParseError> {
+    let mut stack: Vec<Frame> = Vec::new();
+
+    loop {
+        // Go down through opening brackets until reaching a complete value
+        let mut value = match open_value(token.take(), context, stack.len(), iter, options)? {
+            Opened::Value(value) => value,
+            Opened::Array(first) => {
+                check_len(0, options.max_array_len, iter.span)?;
+                stack.push(Frame::Array(Vec::new()));
+                token = Some(first);
+                context = BracketContext::Array;
+                continue;
             }
-            None => {
-                return Err(JsonParseError::ExpectedColonAfterKey(None));
+            Opened::Object(key, key_span) => {
+                check_len(0, options.max_object_keys, key_span)?;
+                stack.push(Frame::Object(JsonObject::new(), key, key_span));
+                context = BracketContext::Object;
+                continue;
             }
         };
 
-        let value = parse_value(None, iter)?;
-        obj.insert(key, value);
-
-        match iter.next() {
-            Some(t) => match t.to_owned() {
-                JsonToken::Comma => {
-                    comma_after_value = true;
-                    continue;
-                }
-                JsonToken::CloseCurlyBracket => {
-                    done = true;
-                    break;
+        // Then hand it to its container, closing every one that ends right after
+        loop {
+            match stack.last_mut() {
+                None => return Ok(value),
+                Some(Frame::Array(arr)) => {
+                    arr.push(value);
+
+                    if let Some(next) = after_array_value(iter, options)? {
+                        check_len(arr.len(), options.max_array_len, iter.span)?;
+                        token = Some(next);
+                        context = BracketContext::Array;
+                        break;
+                    }
                 }
-                _ => {
-                    return Err(JsonParseError::ExpectedCommaOrEndOfObject(Some(
-                        t.to_owned(),
-                    )));
+                Some(Frame::Object(obj, key, key_span)) => {
+                    let member_key = mem::take(key);
+                    if options.reject_duplicate_keys && obj.contains_key(&member_key) {
+                        return Err(JsonParseError::DuplicateKey(member_key, *key_span));
+                    }
+                    obj.insert(member_key, value);
+
+                    if let Some((next_key, next_span)) = after_object_value(iter, options)? {
+                        check_len(obj.len(), options.max_object_keys, next_span)?;
+                        *key = next_key;
+                        *key_span = next_span;
+                        context = BracketContext::Object;
+                        break;
+                    }
                 }
-            },
-            None => {
-                return Err(JsonParseError::ExpectedCommaOrEndOfObject(None));
             }
-        }
-    }
 
-    if done {
-        return Ok(JsonValue::Object(obj));
-    } else {
-        return Err(JsonParseError::ExpectedEndOfObject);
+            value = stack.pop().expect("checked to be non-empty").into_value();
+        }
     }
 }
 
-fn parse_array(iter: &mut dyn Iterator<Item = &JsonToken>) -> Result<JsonValue, JsonParseError> {
-    let mut arr: Vec<JsonValue> = Vec::new();
-
-    let mut done = false;
-    let mut comma_after_value = false;
+/// Parses a single root object or array, leaving whatever follows it in `iter`
+fn parse_root<T: ParserToken, I: Iterator<Item = (T, Span)>>(
+    iter: &mut Tokens<I>,
+    options: &ParserOptions,
+) -> Result<JsonValue, JsonParseError> {
+    let first_token = iter.next().ok_or(JsonParseError::NoTokens)?;
 
-    while let Some(token) = iter.next() {
-        if let JsonToken::CloseSquareBracket = token {
-            if comma_after_value {
-                return Err(JsonParseError::TrailingComma);
-            } else {
-                done = true;
-                break;
-            }
+    match first_token.token() {
+        JsonToken::OpenCurlyBracket | JsonToken::OpenSquareBracket => {
+            parse_value(Some(first_token), BracketContext::Root, iter, options)
         }
-
-        let value = parse_value(Some(token), iter)?;
-        arr.push(value);
-
-        match iter.next() {
-            Some(t) => match t.to_owned() {
-                JsonToken::Comma => {
-                    comma_after_value = true;
-                    continue;
-                }
-                JsonToken::CloseSquareBracket => {
-                    done = true;
-                    break;
-                }
-                _ => {
-                    return Err(JsonParseError::ExpectedCommaOrEndOfArray(Some(
-                        t.to_owned(),
-                    )));
-                }
-            },
-            None => {
-                return Err(JsonParseError::ExpectedCommaOrEndOfArray(None));
-            }
+        token => {
+            BracketContext::Root.check(token, iter.span)?;
+            Err(JsonParseError::ExpectedObjectOrArrayAsRoot(
+                first_token.into_token(),
+                iter.span,
+            ))
         }
     }
+}
 
-    if done {
-        return Ok(JsonValue::Array(arr));
-    } else {
-        return Err(JsonParseError::ExpectedEndOfArray);
+/// Parses the rest of the tokens after the root value, failing if there are any
+fn parse_document<T: ParserToken, I: Iterator<Item = (T, Span)>>(
+    mut iter: Tokens<I>,
+    options: &ParserOptions,
+) -> Result<JsonValue, JsonParseError> {
+    let root = parse_root(&mut iter, options)?;
+
+    if let Some(token) = iter.next() {
+        BracketContext::Root.check(token.token(), iter.span)?;
+        return Err(JsonParseError::TrailingTokens(
+            token.into_token(),
+            iter.span,
+        ));
     }
+
+    Ok(root)
 }
 
-pub fn parser(tokens: &Vec<JsonToken>) -> Result<JsonValue, JsonParseError> {
-    let mut iter = tokens.iter();
+/// Parses the next root value out of a token stream holding several of them
+/// back to back, consuming nothing past its closing bracket
+pub(crate) fn parser_next_root<I: Iterator<Item = (JsonToken, Span)>>(
+    tokens: I,
+    options: &ParserOptions,
+) -> Result<JsonValue, JsonParseError> {
+    parse_root(&mut Tokens::new(tokens), options)
+}
 
-    if let Some(first_token) = iter.next() {
-        match first_token {
-            JsonToken::OpenCurlyBracket => {
-                return Ok(parse_object(&mut iter)?);
-            }
-            JsonToken::OpenSquareBracket => {
-                return Ok(parse_array(&mut iter)?);
-            }
-            _ => {
-                return Err(JsonParseError::ExpectedObjectOrArrayAsRoot(
-                    first_token.to_owned(),
-                ));
-            }
-        };
-    } else {
-        return Err(JsonParseError::NoTokens);
-    }
+pub fn parser(tokens: &[(JsonToken, Span)]) -> Result<JsonValue, JsonParseError> {
+    parser_with_options(tokens, &ParserOptions::default())
+}
+
+/// Parses with either [`ParserOptions`] or the parser half of
+/// [`ParseOptions`](crate::ParseOptions)
+pub fn parser_with_options(
+    tokens: &[(JsonToken, Span)],
+    options: &impl AsRef<ParserOptions>,
+) -> Result<JsonValue, JsonParseError> {
+    // Borrowing the tokens leaves numbers and punctuation uncloned
+    let tokens = tokens.iter().map(|(token, span)| (token, *span));
+    parse_document(Tokens::new(tokens), options.as_ref())
+}
+
+/// Parses tokens as they are produced, so the whole token stream never has
+/// to be held in memory at once
+pub fn parser_from_iter<I: IntoIterator<Item = (JsonToken, Span)>>(
+    tokens: I,
+    options: &impl AsRef<ParserOptions>,
+) -> Result<JsonValue, JsonParseError> {
+    parse_document(Tokens::new(tokens.into_iter()), options.as_ref())
 }
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
+    use crate::lexer::{lexer, lexer_with_options, JsonToken, Lexer, LexerOptions, Span};
+
+    use super::{
+        parser, parser_from_iter, parser_with_options, BracketContext, JsonNumber, JsonObject,
+        JsonParseError, JsonValue, ParserOptions, DEFAULT_MAX_DEPTH,
+    };
 
-    use crate::lexer::JsonToken;
+    fn at(col: usize) -> Span {
+        Span { line: 1, col }
+    }
 
-    use super::{parser, JsonParseError, JsonValue};
+    /// Lays the tokens out on a single line, one column apart
+    fn spanned(tokens: Vec<JsonToken>) -> Vec<(JsonToken, Span)> {
+        tokens
+            .into_iter()
+            .enumerate()
+            .map(|(i, token)| (token, at(i + 1)))
+            .collect()
+    }
 
     #[test]
     fn test_empty_input() {
         let input = vec![];
-        assert_eq!(parser(&input), Err(JsonParseError::NoTokens));
+        assert_eq!(parser(&spanned(input)), Err(JsonParseError::NoTokens));
     }
 
     #[test]
@@ -254,21 +598,30 @@ mod tests {
         let input = vec![invalid_token.to_owned()];
 
         assert_eq!(
-            parser(&input),
-            Err(JsonParseError::ExpectedObjectOrArrayAsRoot(invalid_token))
+            parser(&spanned(input)),
+            Err(JsonParseError::ExpectedObjectOrArrayAsRoot(
+                invalid_token,
+                at(1)
+            ))
         );
     }
 
     #[test]
     fn test_missing_object_close() {
         let input = vec![JsonToken::OpenCurlyBracket];
-        assert_eq!(parser(&input), Err(JsonParseError::ExpectedEndOfObject));
+        assert_eq!(
+            parser(&spanned(input)),
+            Err(JsonParseError::ExpectedEndOfObject(at(1)))
+        );
     }
 
     #[test]
     fn test_missing_array_close() {
         let input = vec![JsonToken::OpenSquareBracket];
-        assert_eq!(parser(&input), Err(JsonParseError::ExpectedEndOfArray));
+        assert_eq!(
+            parser(&spanned(input)),
+            Err(JsonParseError::ExpectedEndOfArray(at(1)))
+        );
     }
 
     #[test]
@@ -282,8 +635,8 @@ mod tests {
         ];
 
         assert_eq!(
-            parser(&input),
-            Err(JsonParseError::ExpectedObjectKey(invalid_token))
+            parser(&spanned(input)),
+            Err(JsonParseError::ExpectedObjectKey(invalid_token, at(2)))
         );
     }
 
@@ -295,8 +648,8 @@ mod tests {
         ];
 
         assert_eq!(
-            parser(&input),
-            Err(JsonParseError::ExpectedColonAfterKey(None))
+            parser(&spanned(input)),
+            Err(JsonParseError::ExpectedColonAfterKey(None, at(2)))
         );
     }
 
@@ -308,7 +661,10 @@ mod tests {
             JsonToken::Colon,
         ];
 
-        assert_eq!(parser(&input), Err(JsonParseError::InvalidValue(None)));
+        assert_eq!(
+            parser(&spanned(input)),
+            Err(JsonParseError::InvalidValue(None, at(3)))
+        );
     }
 
     #[test]
@@ -323,8 +679,8 @@ mod tests {
         ];
 
         assert_eq!(
-            parser(&input),
-            Err(JsonParseError::InvalidValue(Some(invalid_token)))
+            parser(&spanned(input)),
+            Err(JsonParseError::InvalidValue(Some(invalid_token), at(4)))
         );
     }
 
@@ -335,8 +691,8 @@ mod tests {
         let input = vec![JsonToken::OpenSquareBracket, invalid_token.to_owned()];
 
         assert_eq!(
-            parser(&input),
-            Err(JsonParseError::InvalidValue(Some(invalid_token)))
+            parser(&spanned(input)),
+            Err(JsonParseError::InvalidValue(Some(invalid_token), at(2)))
         );
     }
 
@@ -350,8 +706,8 @@ mod tests {
         ];
 
         assert_eq!(
-            parser(&input),
-            Err(JsonParseError::ExpectedCommaOrEndOfObject(None))
+            parser(&spanned(input)),
+            Err(JsonParseError::ExpectedCommaOrEndOfObject(None, at(4)))
         );
     }
 
@@ -363,8 +719,8 @@ mod tests {
         ];
 
         assert_eq!(
-            parser(&input),
-            Err(JsonParseError::ExpectedCommaOrEndOfArray(None))
+            parser(&spanned(input)),
+            Err(JsonParseError::ExpectedCommaOrEndOfArray(None, at(2)))
         );
     }
 
@@ -378,66 +734,131 @@ mod tests {
         ];
 
         assert_eq!(
-            parser(&input),
-            Err(JsonParseError::InvalidNumberValue(invalid_number))
+            parser(&spanned(input)),
+            Err(JsonParseError::InvalidNumberValue(invalid_number, at(2)))
         );
     }
 
     #[test]
-    fn test_invalid_true() {
-        let invalid_true = String::from("trua");
+    fn test_integer_and_float_numbers() -> Result<(), JsonParseError> {
+        let input = lexer("[9007199254740993, 1.0, -0, 20]").unwrap();
 
-        let input = vec![
-            JsonToken::OpenSquareBracket,
-            JsonToken::Boolean(invalid_true.to_owned()),
-        ];
+        let expected = JsonValue::Array(vec![
+            JsonValue::Number(JsonNumber::Int(9007199254740993)),
+            JsonValue::Number(JsonNumber::Float(1.0)),
+            JsonValue::Number(JsonNumber::Float(-0.0)),
+            JsonValue::Number(JsonNumber::Int(20)),
+        ]);
 
-        assert_eq!(
-            parser(&input),
-            Err(JsonParseError::InvalidBooleanValue(invalid_true))
-        );
+        let value = parser(&input)?;
+        assert_eq!(value, expected);
+        assert_eq!(value.to_string(), "[9007199254740993,1.0,-0.0,20]");
+
+        Ok(())
     }
 
+    #[cfg(feature = "rust_decimal")]
     #[test]
-    fn test_invalid_false() {
-        let invalid_false = String::from("falso");
+    fn test_parse_decimals() -> Result<(), JsonParseError> {
+        let input = lexer("[0.1, 0.2, 0.3, 19.99, 0.10, 7]").unwrap();
+        let options = ParserOptions {
+            parse_decimals: true,
+            ..Default::default()
+        };
 
-        let input = vec![
-            JsonToken::OpenSquareBracket,
-            JsonToken::Boolean(invalid_false.to_owned()),
-        ];
+        let value = parser_with_options(&input, &options)?;
+        let decimal = |i: usize| match value[i] {
+            JsonValue::Number(JsonNumber::Decimal(n)) => n,
+            _ => panic!("expected a decimal at {}", i),
+        };
+
+        // Exact where `0.1 + 0.2 != 0.3` with floats
+        assert_eq!(decimal(0) + decimal(1), decimal(2));
+        assert_ne!(value[0].as_f64().unwrap() + value[1].as_f64().unwrap(), 0.3);
 
+        assert_eq!(value[5], JsonValue::Number(JsonNumber::Int(7)));
+        assert_eq!(value.to_string(), "[0.1,0.2,0.3,19.99,0.10,7]");
         assert_eq!(
-            parser(&input),
-            Err(JsonParseError::InvalidBooleanValue(invalid_false))
+            parser_with_options(&lexer(&value.to_string()).unwrap(), &options)?,
+            value
         );
+
+        Ok(())
     }
 
     #[test]
-    fn test_invalid_null() {
-        let invalid_null = String::from("nulo");
+    fn test_preserve_number_text() -> Result<(), JsonParseError> {
+        let input = lexer("[0.10, 1e2, -0, 20]").unwrap();
+        let options = ParserOptions {
+            preserve_number_text: true,
+            ..Default::default()
+        };
 
-        let input = vec![
-            JsonToken::OpenSquareBracket,
-            JsonToken::Null(invalid_null.to_owned()),
-        ];
+        let value = parser_with_options(&input, &options)?;
 
-        assert_eq!(
-            parser(&input),
-            Err(JsonParseError::InvalidNullValue(invalid_null))
-        );
+        assert_eq!(value[0], JsonValue::Number(JsonNumber::Raw("0.10".into())));
+        assert_eq!(value.to_string(), "[0.10,1e2,-0,20]");
+        assert_eq!(parser(&input)?.to_string(), "[0.1,100.0,-0.0,20]");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_exponent_numbers() -> Result<(), JsonParseError> {
+        let input = lexer("[1e10, -2.5E-3, 0e0]").unwrap();
+
+        let expected = JsonValue::Array(vec![
+            JsonValue::Number(1e10.into()),
+            JsonValue::Number((-2.5e-3).into()),
+            JsonValue::Number(0.0.into()),
+        ]);
+
+        assert_eq!(parser(&input)?, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_exponent_numbers() {
+        for invalid_number in ["1e", "1e+", "1.2.3"] {
+            let input = vec![
+                JsonToken::OpenSquareBracket,
+                JsonToken::Number(invalid_number.into()),
+            ];
+
+            assert_eq!(
+                parser(&spanned(input)),
+                Err(JsonParseError::InvalidNumberValue(
+                    invalid_number.into(),
+                    at(2)
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn test_out_of_range_numbers() {
+        for number in ["1e400", "-1e400", "2.5E+999"] {
+            assert_eq!(
+                parser(&lexer(&format!("[{}]", number)).unwrap()),
+                Err(JsonParseError::InvalidNumberValue(number.into(), at(2)))
+            );
+        }
     }
 
     #[test]
     fn test_trailing_comma_in_object() {
         let input = vec![
             JsonToken::OpenSquareBracket,
-            JsonToken::Null("null".into()),
+            JsonToken::Null,
             JsonToken::Comma,
             JsonToken::CloseSquareBracket,
         ];
 
-        assert_eq!(parser(&input), Err(JsonParseError::TrailingComma));
+        assert_eq!(
+            parser(&spanned(input)),
+            Err(JsonParseError::TrailingComma(at(3)))
+        );
     }
 
     #[test]
@@ -451,7 +872,10 @@ mod tests {
             JsonToken::CloseCurlyBracket,
         ];
 
-        assert_eq!(parser(&input), Err(JsonParseError::TrailingComma));
+        assert_eq!(
+            parser(&spanned(input)),
+            Err(JsonParseError::TrailingComma(at(5)))
+        );
     }
 
     #[test]
@@ -461,22 +885,22 @@ mod tests {
             JsonToken::OpenCurlyBracket,
             JsonToken::String("money".into()),
             JsonToken::Colon,
-            JsonToken::Null("null".into()),
+            JsonToken::Null,
             JsonToken::Comma,
             JsonToken::String("age".into()),
             JsonToken::Colon,
             JsonToken::Number("20".into()),
             JsonToken::CloseCurlyBracket,
             JsonToken::Comma,
-            JsonToken::Boolean("true".into()),
+            JsonToken::True,
             JsonToken::Comma,
-            JsonToken::Boolean("false".into()),
+            JsonToken::False,
             JsonToken::CloseSquareBracket,
         ];
 
-        let mut obj: HashMap<String, JsonValue> = HashMap::new();
+        let mut obj = JsonObject::new();
         obj.insert("money".into(), JsonValue::Null);
-        obj.insert("age".into(), JsonValue::Number(20.0));
+        obj.insert("age".into(), JsonValue::Number(20.into()));
 
         let arr = vec![
             JsonValue::Object(obj),
@@ -486,10 +910,424 @@ mod tests {
 
         let expected = JsonValue::Array(arr);
 
-        let json = parser(&input)?;
+        let json = parser(&spanned(input))?;
 
         assert_eq!(json, expected);
 
         Ok(())
     }
+
+    #[test]
+    fn test_missing_colon_position() {
+        let input = lexer("{\n  \"name\": \"fulano\",\n  \"age\" 20\n}").unwrap();
+        let err = parser(&input).unwrap_err();
+
+        assert_eq!(
+            err,
+            JsonParseError::ExpectedColonAfterKey(
+                Some(JsonToken::Number("20".into())),
+                Span { line: 3, col: 9 }
+            )
+        );
+        assert_eq!(
+            err.to_string(),
+            "Expected colon after key, got `Some(Number(\"20\"))` at line 3, column 9"
+        );
+    }
+
+    #[test]
+    fn test_trailing_comma_position() {
+        let input = lexer("[\n  1,\n  2,\n]").unwrap();
+        let err = parser(&input).unwrap_err();
+
+        assert_eq!(err, JsonParseError::TrailingComma(Span { line: 3, col: 4 }));
+        assert_eq!(err.to_string(), "Trailing comma at line 3, column 4");
+    }
+
+    #[test]
+    #[cfg(not(feature = "btreemap"))]
+    fn test_object_key_order() -> Result<(), JsonParseError> {
+        let input = lexer(r#"{"b": 1, "a": 2, "c": 3}"#).unwrap();
+
+        let json = parser(&input)?;
+        let keys: Vec<&String> = match &json {
+            JsonValue::Object(obj) => obj.keys().collect(),
+            _ => unreachable!(),
+        };
+
+        assert_eq!(keys, vec!["b", "a", "c"]);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "btreemap")]
+    fn test_object_keys_sorted() -> Result<(), JsonParseError> {
+        let input = lexer(r#"{"b": 1, "a": {"d": 2, "c": 3}, "c": 4}"#).unwrap();
+
+        let json = parser(&input)?;
+        let keys: Vec<&String> = match &json {
+            JsonValue::Object(obj) => obj.keys().collect(),
+            _ => unreachable!(),
+        };
+
+        assert_eq!(keys, vec!["a", "b", "c"]);
+        assert_eq!(json["a"]["c"], JsonValue::Number(3.into()));
+        assert_eq!(json.get("b"), Some(&JsonValue::Number(1.into())));
+        assert_eq!(json.get("z"), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicate_key_last_wins() -> Result<(), JsonParseError> {
+        let input = lexer(r#"{"a": 1, "b": 2, "a": 3}"#).unwrap();
+
+        let mut obj = JsonObject::new();
+        obj.insert("a".into(), JsonValue::Number(3.into()));
+        obj.insert("b".into(), JsonValue::Number(2.into()));
+
+        assert_eq!(parser(&input)?, JsonValue::Object(obj));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicate_key_strict() {
+        let input = lexer(r#"{"a": 1, "b": {"a": 2}, "a": 3}"#).unwrap();
+        let options = ParserOptions {
+            reject_duplicate_keys: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            parser_with_options(&input, &options),
+            Err(JsonParseError::DuplicateKey(
+                "a".into(),
+                Span { line: 1, col: 25 }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_trailing_tokens_after_root() {
+        let input = lexer("{}{}").unwrap();
+        assert_eq!(
+            parser(&input),
+            Err(JsonParseError::TrailingTokens(
+                JsonToken::OpenCurlyBracket,
+                Span { line: 1, col: 3 }
+            ))
+        );
+
+        let input = lexer("[] true").unwrap();
+        assert_eq!(
+            parser(&input),
+            Err(JsonParseError::TrailingTokens(
+                JsonToken::True,
+                Span { line: 1, col: 4 }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_root_surrounded_by_whitespace() -> Result<(), JsonParseError> {
+        let input = lexer(" \n\t[1] \n ").unwrap();
+        assert_eq!(
+            parser(&input)?,
+            JsonValue::Array(vec![JsonValue::Number(1.into())])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_trailing_commas_lenient() -> Result<(), JsonParseError> {
+        let options = ParserOptions {
+            allow_trailing_commas: true,
+            ..Default::default()
+        };
+
+        let input = lexer("[1, 2,]").unwrap();
+        assert_eq!(
+            parser_with_options(&input, &options)?,
+            JsonValue::Array(vec![
+                JsonValue::Number(1.into()),
+                JsonValue::Number(2.into())
+            ])
+        );
+
+        let input = lexer(r#"{"a": 1,}"#).unwrap();
+        let mut obj = JsonObject::new();
+        obj.insert("a".into(), JsonValue::Number(1.into()));
+
+        assert_eq!(
+            parser_with_options(&input, &options)?,
+            JsonValue::Object(obj)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_trailing_commas_strict() {
+        let input = lexer("[1, 2,]").unwrap();
+        assert_eq!(
+            parser(&input),
+            Err(JsonParseError::TrailingComma(Span { line: 1, col: 6 }))
+        );
+
+        let input = lexer(r#"{"a": 1,}"#).unwrap();
+        assert_eq!(
+            parser(&input),
+            Err(JsonParseError::TrailingComma(Span { line: 1, col: 8 }))
+        );
+    }
+
+    #[test]
+    fn test_only_single_trailing_comma_lenient() {
+        let options = ParserOptions {
+            allow_trailing_commas: true,
+            ..Default::default()
+        };
+
+        let input = lexer("[1,,]").unwrap();
+        assert_eq!(
+            parser_with_options(&input, &options),
+            Err(JsonParseError::InvalidValue(
+                Some(JsonToken::Comma),
+                Span { line: 1, col: 4 }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_from_lexing_iterator() {
+        let input = r#"{"name": "fulano", "roles": ["admin", null], "age": 20}"#;
+
+        let tokens = Lexer::new(input, &LexerOptions::default())
+            .map(|token| token.unwrap())
+            .map(|(token, span)| (token.into_owned(), span));
+        let value = parser_from_iter(tokens, &ParserOptions::default());
+
+        assert_eq!(value, parser(&lexer(input).unwrap()));
+    }
+
+    #[test]
+    fn test_mismatched_closing_brackets() {
+        let input = lexer("[}").unwrap();
+        assert_eq!(
+            parser(&input),
+            Err(JsonParseError::UnexpectedClosingBracket(
+                '}',
+                BracketContext::Array,
+                at(2)
+            ))
+        );
+
+        let input = lexer("{]").unwrap();
+        assert_eq!(
+            parser(&input),
+            Err(JsonParseError::UnexpectedClosingBracket(
+                ']',
+                BracketContext::Object,
+                at(2)
+            ))
+        );
+
+        let input = lexer(r#"{"a":[}"#).unwrap();
+        assert_eq!(
+            parser(&input),
+            Err(JsonParseError::UnexpectedClosingBracket(
+                '}',
+                BracketContext::Array,
+                at(7)
+            ))
+        );
+
+        let input = lexer("[1}").unwrap();
+        assert_eq!(
+            parser(&input),
+            Err(JsonParseError::UnexpectedClosingBracket(
+                '}',
+                BracketContext::Array,
+                at(3)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_stray_closing_brackets() {
+        let input = lexer("}").unwrap();
+        assert_eq!(
+            parser(&input),
+            Err(JsonParseError::UnexpectedClosingBracket(
+                '}',
+                BracketContext::Root,
+                at(1)
+            ))
+        );
+
+        let input = lexer("[1]]").unwrap();
+        let err = parser(&input).unwrap_err();
+        assert_eq!(
+            err,
+            JsonParseError::UnexpectedClosingBracket(']', BracketContext::Root, at(4))
+        );
+        assert_eq!(
+            err.to_string(),
+            "Unexpected closing bracket `]` outside of any object or array at line 1, column 4"
+        );
+    }
+
+    #[test]
+    fn test_matching_closing_bracket_in_value_position() {
+        let input = lexer(r#"{"a":}"#).unwrap();
+        assert_eq!(
+            parser(&input),
+            Err(JsonParseError::InvalidValue(
+                Some(JsonToken::CloseCurlyBracket),
+                at(6)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_unquoted_keys() {
+        let options = LexerOptions {
+            allow_unquoted_keys: true,
+            ..Default::default()
+        };
+        let tokens = lexer_with_options("{a: 1, _b: 2}", &options).unwrap();
+
+        let mut expected = JsonObject::new();
+        expected.insert("a".into(), JsonValue::Number(1.into()));
+        expected.insert("_b".into(), JsonValue::Number(2.into()));
+        assert_eq!(parser(&tokens), Ok(JsonValue::Object(expected)));
+
+        let tokens = lexer_with_options("{NaN: 1, Infinity: 2}", &options).unwrap();
+        let json = parser(&tokens).unwrap();
+        assert_eq!(json["NaN"], JsonValue::Number(1.into()));
+        assert_eq!(json["Infinity"], JsonValue::Number(2.into()));
+
+        // Identifiers are keys only, never values
+        let tokens = lexer_with_options("{a: b}", &options).unwrap();
+        assert_eq!(
+            parser(&tokens),
+            Err(JsonParseError::InvalidValue(
+                Some(JsonToken::Identifier("b".into())),
+                at(5)
+            ))
+        );
+
+        assert!(lexer("{a: 1, _b: 2}").is_err());
+    }
+
+    #[test]
+    fn test_non_finite_numbers() {
+        let options = LexerOptions {
+            allow_non_finite_numbers: true,
+            ..Default::default()
+        };
+        let tokens = lexer_with_options("[NaN, Infinity, -Infinity]", &options).unwrap();
+        let json = parser(&tokens).unwrap();
+
+        assert!(json[0].as_f64().unwrap().is_nan());
+        assert_eq!(json[1].as_f64(), Some(f64::INFINITY));
+        assert_eq!(json[2].as_f64(), Some(f64::NEG_INFINITY));
+
+        // Standard JSON can't hold them, so they serialize as null
+        assert_eq!(json.to_string(), "[null,null,null]");
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        const DEPTH: usize = 100_000;
+
+        // The parser itself doesn't recurse, so only `max_depth` stops it
+        let unlimited = ParserOptions {
+            max_depth: None,
+            ..Default::default()
+        };
+        let unclosed = "[".repeat(DEPTH);
+        assert_eq!(
+            parser_with_options(&lexer(&unclosed).unwrap(), &unlimited),
+            Err(JsonParseError::ExpectedEndOfArray(at(DEPTH)))
+        );
+
+        let input = format!(
+            "{}{}{}",
+            "[{\"a\":".repeat(DEPTH),
+            "null",
+            "}]".repeat(DEPTH)
+        );
+
+        // Dropping doesn't recurse either, so an unlimited value goes away fine
+        let json = parser_with_options(&lexer(&input).unwrap(), &unlimited).unwrap();
+        assert!(json[0]["a"][0]["a"].as_array().is_some());
+        drop(json);
+
+        // By default a deep document fails, since comparing or printing it
+        // would still recurse
+        assert_eq!(
+            parser(&lexer(&input).unwrap()),
+            Err(JsonParseError::MaxDepthExceeded(
+                DEFAULT_MAX_DEPTH,
+                at(DEFAULT_MAX_DEPTH / 2 * 6 + 1)
+            ))
+        );
+
+        let input = format!(
+            "{}{}",
+            "[".repeat(DEFAULT_MAX_DEPTH),
+            "]".repeat(DEFAULT_MAX_DEPTH)
+        );
+        let json = parser(&lexer(&input).unwrap()).unwrap();
+        assert_eq!(json.depth(), DEFAULT_MAX_DEPTH);
+        assert_eq!(json.to_string(), input);
+    }
+
+    #[test]
+    fn test_max_array_len() {
+        let options = ParserOptions {
+            max_array_len: Some(10_000),
+            ..Default::default()
+        };
+        let array = |len: usize| format!("[{}]", vec!["0"; len].join(","));
+
+        let json = parser_with_options(&lexer(&array(10_000)).unwrap(), &options).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 10_000);
+
+        assert_eq!(
+            parser_with_options(&lexer(&array(10_001)).unwrap(), &options),
+            Err(JsonParseError::TooManyElements(10_000, at(20_002)))
+        );
+        assert!(parser(&lexer(&array(10_001)).unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_max_object_keys() {
+        let options = ParserOptions {
+            max_object_keys: Some(2),
+            max_array_len: Some(0),
+            ..Default::default()
+        };
+
+        let tokens = lexer(r#"{"a": {}, "b": []}"#).unwrap();
+        assert!(parser_with_options(&tokens, &options).is_ok());
+
+        let tokens = lexer(r#"{"a": 1, "b": 2, "c": 3}"#).unwrap();
+        let err = parser_with_options(&tokens, &options).unwrap_err();
+        assert_eq!(err, JsonParseError::TooManyElements(2, at(18)));
+        assert_eq!(
+            err.to_string(),
+            "Too many elements, no more than 2 are allowed, at line 1, column 18"
+        );
+
+        let tokens = lexer("[1]").unwrap();
+        assert_eq!(
+            parser_with_options(&tokens, &options),
+            Err(JsonParseError::TooManyElements(0, at(2)))
+        );
+    }
 }
@@ -0,0 +1,452 @@
+use crate::{
+    parser::{JsonObject, JsonValue},
+    pointer::{escape_token, parent_mut, parse_index, remove_at, PointerError},
+};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum PatchError {
+    #[error("A JSON Patch must be an array of operations")]
+    NotAnArray,
+    #[error("Invalid operation {0}, {1}")]
+    InvalidOperation(usize, String),
+    #[error("Operation {0} failed, {1}")]
+    Pointer(usize, PointerError),
+    #[error("Operation {0} failed, cannot move `{1}` into itself")]
+    MoveIntoItself(usize, String),
+    #[error("Operation {0} failed, the value at `{1}` is not the expected one")]
+    TestFailed(usize, String),
+}
+
+/// Adds the value at `path` the way the `add` operation does, which unlike
+/// [`JsonValue::set_pointer`] inserts into arrays instead of replacing
+fn add(target: &mut JsonValue, path: &str, value: JsonValue) -> Result<(), PointerError> {
+    if path.is_empty() {
+        *target = value;
+        return Ok(());
+    }
+
+    let (parent, last, at) = parent_mut(target, path)?;
+    let at = format!("{}/{}", at, escape_token(&last));
+
+    match parent {
+        JsonValue::Object(obj) => {
+            obj.insert(last, value);
+        }
+        JsonValue::Array(arr) if last == "-" => arr.push(value),
+        JsonValue::Array(arr) => {
+            let i = parse_index(&last).ok_or(PointerError::InvalidIndex(at.clone()))?;
+            if i > arr.len() {
+                return Err(PointerError::NotFound(at));
+            }
+            arr.insert(i, value);
+        }
+        other => return Err(PointerError::NotAContainer(at, other.type_name())),
+    }
+
+    Ok(())
+}
+
+/// Member of an operation object that has to be a string
+fn str_member<'a>(op: &'a JsonValue, i: usize, name: &str) -> Result<&'a str, PatchError> {
+    op.get(name).and_then(JsonValue::as_str).ok_or_else(|| {
+        PatchError::InvalidOperation(i, format!("expected a string `{}` member", name))
+    })
+}
+
+fn apply_operation(target: &mut JsonValue, op: &JsonValue, i: usize) -> Result<(), PatchError> {
+    let name = str_member(op, i, "op")?;
+    let path = str_member(op, i, "path")?;
+    let not_found = || PatchError::Pointer(i, PointerError::NotFound(path.to_string()));
+    let value = || {
+        op.get("value").cloned().ok_or_else(|| {
+            PatchError::InvalidOperation(i, format!("expected a `value` member for `{}`", name))
+        })
+    };
+
+    match name {
+        "add" => add(target, path, value()?).map_err(|err| PatchError::Pointer(i, err)),
+        "remove" => remove_at(target, path)
+            .map(drop)
+            .map_err(|err| PatchError::Pointer(i, err)),
+        "replace" => {
+            let value = value()?;
+            target.pointer(path).ok_or_else(not_found)?;
+            target
+                .set_pointer(path, value)
+                .map_err(|err| PatchError::Pointer(i, err))
+        }
+        "move" => {
+            let from = str_member(op, i, "from")?;
+            // Moving a value onto itself leaves it where it is
+            if from == path {
+                return target.pointer(from).map(drop).ok_or_else(not_found);
+            }
+            if path.starts_with(from) && path[from.len()..].starts_with('/') {
+                return Err(PatchError::MoveIntoItself(i, from.to_string()));
+            }
+
+            let value = remove_at(target, from).map_err(|err| PatchError::Pointer(i, err))?;
+            add(target, path, value).map_err(|err| PatchError::Pointer(i, err))
+        }
+        "copy" => {
+            let from = str_member(op, i, "from")?;
+            let value = target
+                .pointer(from)
+                .cloned()
+                .ok_or_else(|| PatchError::Pointer(i, PointerError::NotFound(from.to_string())))?;
+            add(target, path, value).map_err(|err| PatchError::Pointer(i, err))
+        }
+        "test" => match target.pointer(path) {
+            Some(found) if found.value_eq(&value()?) => Ok(()),
+            _ => Err(PatchError::TestFailed(i, path.to_string())),
+        },
+        _ => Err(PatchError::InvalidOperation(
+            i,
+            format!("unknown op `{}`", name),
+        )),
+    }
+}
+
+impl JsonValue {
+    /// Applies an RFC 6902 JSON Patch, an array of operations like
+    /// `{"op": "add", "path": "/a", "value": 1}`. The patch is applied as a
+    /// whole: if any operation fails, including a `test`, the value is left
+    /// untouched
+    ///
+    /// ```
+    /// use crusty_json::JsonValue;
+    ///
+    /// let mut json: JsonValue = r#"{"tags": ["a"]}"#.parse().unwrap();
+    /// let patch: JsonValue = r#"[
+    ///     {"op": "test", "path": "/tags/0", "value": "a"},
+    ///     {"op": "add", "path": "/tags/0", "value": "b"}
+    /// ]"#
+    /// .parse()
+    /// .unwrap();
+    ///
+    /// json.apply_patch(&patch).unwrap();
+    /// assert_eq!(json.to_string(), r#"{"tags":["b","a"]}"#);
+    /// ```
+    pub fn apply_patch(&mut self, patch: &JsonValue) -> Result<(), PatchError> {
+        let ops = patch.as_array().ok_or(PatchError::NotAnArray)?;
+
+        let mut patched = self.clone();
+        for (i, op) in ops.iter().enumerate() {
+            apply_operation(&mut patched, op, i)?;
+        }
+
+        *self = patched;
+        Ok(())
+    }
+
+    /// Applies an RFC 7386 JSON Merge Patch: objects in the patch are merged
+    /// into the value key by key, where `null` removes the key, and anything
+    /// else replaces the value it lands on wholesale
+    ///
+    /// ```
+    /// use crusty_json::JsonValue;
+    ///
+    /// let mut json: JsonValue = r#"{"a": 1, "b": {"c": 2}}"#.parse().unwrap();
+    /// let patch: JsonValue = r#"{"a": null, "b": {"d": 3}}"#.parse().unwrap();
+    ///
+    /// json.apply_merge_patch(&patch);
+    /// assert_eq!(json.to_string(), r#"{"b":{"c":2,"d":3}}"#);
+    /// ```
+    pub fn apply_merge_patch(&mut self, patch: &JsonValue) {
+        let JsonValue::Object(patch) = patch else {
+            *self = patch.clone();
+            return;
+        };
+
+        if !matches!(self, JsonValue::Object(_)) {
+            *self = JsonValue::Object(JsonObject::new());
+        }
+        let JsonValue::Object(obj) = self else {
+            unreachable!("replaced by an object above");
+        };
+
+        for (key, value) in patch {
+            if value.is_null() {
+                #[cfg(not(feature = "btreemap"))]
+                obj.shift_remove(key);
+                #[cfg(feature = "btreemap")]
+                obj.remove(key);
+            } else {
+                obj.entry(key.clone())
+                    .or_insert(JsonValue::Null)
+                    .apply_merge_patch(value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PatchError;
+    use crate::{parser::JsonValue, pointer::PointerError};
+
+    fn patched(doc: JsonValue, patch: JsonValue) -> Result<JsonValue, PatchError> {
+        let mut doc = doc;
+        doc.apply_patch(&patch)?;
+        Ok(doc)
+    }
+
+    #[test]
+    fn test_add() {
+        let doc = json!({"a": {"b": 1}, "list": [1, 3]});
+        let patch = json!([
+            {"op": "add", "path": "/a/c", "value": 2},
+            {"op": "add", "path": "/a/b", "value": [0]},
+            {"op": "add", "path": "/list/1", "value": 2},
+            {"op": "add", "path": "/list/-", "value": 4},
+            {"op": "add", "path": "/list/4", "value": 5}
+        ]);
+
+        assert_eq!(
+            patched(doc, patch),
+            Ok(json!({"a": {"b": [0], "c": 2}, "list": [1, 2, 3, 4, 5]}))
+        );
+        assert_eq!(
+            patched(json!({}), json!([{"op": "add", "path": "", "value": [1]}])),
+            Ok(json!([1]))
+        );
+    }
+
+    #[test]
+    fn test_remove() {
+        let doc = json!({"a": 1, "b": 2, "list": [1, 2, 3]});
+        let patch = json!([
+            {"op": "remove", "path": "/a"},
+            {"op": "remove", "path": "/list/0"}
+        ]);
+
+        assert_eq!(patched(doc, patch), Ok(json!({"b": 2, "list": [2, 3]})));
+
+        // Pointer errors come through as they are rather than as not found
+        assert_eq!(
+            patched(json!({"a": 1}), json!([{"op": "remove", "path": "a"}])),
+            Err(PatchError::Pointer(
+                0,
+                PointerError::InvalidPointer("a".into())
+            ))
+        );
+        assert_eq!(
+            patched(
+                json!({"list": [1]}),
+                json!([{"op": "remove", "path": "/list/x"}])
+            ),
+            Err(PatchError::Pointer(
+                0,
+                PointerError::InvalidIndex("/list/x".into())
+            ))
+        );
+        assert_eq!(
+            patched(json!({}), json!([{"op": "remove", "path": "/a"}])),
+            Err(PatchError::Pointer(0, PointerError::NotFound("/a".into())))
+        );
+    }
+
+    #[test]
+    fn test_replace() {
+        let doc = json!({"a": 1, "list": [1, 2]});
+        let patch = json!([
+            {"op": "replace", "path": "/a", "value": {"b": null}},
+            {"op": "replace", "path": "/list/1", "value": 3}
+        ]);
+
+        assert_eq!(
+            patched(doc, patch),
+            Ok(json!({"a": {"b": null}, "list": [1, 3]}))
+        );
+
+        // Unlike `add`, the value has to be there already
+        assert_eq!(
+            patched(
+                json!({}),
+                json!([{"op": "replace", "path": "/a", "value": 1}])
+            ),
+            Err(PatchError::Pointer(0, PointerError::NotFound("/a".into())))
+        );
+    }
+
+    #[test]
+    fn test_move() {
+        let doc = json!({"a": {"b": 1}, "list": [1, 2, 3]});
+        let patch = json!([
+            {"op": "move", "from": "/a/b", "path": "/c"},
+            {"op": "move", "from": "/list/0", "path": "/list/-"}
+        ]);
+
+        assert_eq!(
+            patched(doc, patch),
+            Ok(json!({"a": {}, "list": [2, 3, 1], "c": 1}))
+        );
+        assert_eq!(
+            patched(
+                json!({"a": {"b": {}}}),
+                json!([{"op": "move", "from": "/a", "path": "/a/b/c"}])
+            ),
+            Err(PatchError::MoveIntoItself(0, "/a".into()))
+        );
+    }
+
+    #[test]
+    fn test_move_onto_itself() {
+        let doc = json!({"a": {"b": 1}, "list": [1, 2]});
+
+        for path in ["", "/a", "/a/b", "/list/0"] {
+            assert_eq!(
+                patched(
+                    doc.clone(),
+                    json!([{"op": "move", "from": path, "path": path}])
+                ),
+                Ok(doc.clone()),
+                "{:?}",
+                path
+            );
+        }
+        assert_eq!(
+            patched(doc, json!([{"op": "move", "from": "/c", "path": "/c"}])),
+            Err(PatchError::Pointer(0, PointerError::NotFound("/c".into())))
+        );
+    }
+
+    #[test]
+    fn test_copy() {
+        let doc = json!({"a": {"b": [1]}, "list": []});
+        let patch = json!([
+            {"op": "copy", "from": "/a/b", "path": "/list/0"},
+            {"op": "copy", "from": "/a", "path": "/c"}
+        ]);
+
+        assert_eq!(
+            patched(doc, patch),
+            Ok(json!({"a": {"b": [1]}, "list": [[1]], "c": {"b": [1]}}))
+        );
+    }
+
+    #[test]
+    fn test_test() {
+        let doc = json!({"a": [1, "two"]});
+        let patch = json!([
+            {"op": "test", "path": "/a", "value": [1, "two"]},
+            {"op": "test", "path": "/a/1", "value": "two"}
+        ]);
+
+        assert_eq!(patched(doc.clone(), patch), Ok(doc));
+    }
+
+    #[test]
+    fn test_test_compares_numbers_by_value() {
+        let doc = json!({"a": 1, "b": [2.0]});
+        let patch = json!([
+            {"op": "test", "path": "/a", "value": 1.0},
+            {"op": "test", "path": "/b", "value": [2]}
+        ]);
+
+        assert_eq!(patched(doc.clone(), patch), Ok(doc.clone()));
+        assert_eq!(
+            patched(doc, json!([{"op": "test", "path": "/a", "value": 1.5}])),
+            Err(PatchError::TestFailed(0, "/a".into()))
+        );
+    }
+
+    #[test]
+    fn test_failing_test_leaves_value_untouched() {
+        let mut doc = json!({"a": 1, "b": 2});
+        let patch = json!([
+            {"op": "remove", "path": "/a"},
+            {"op": "test", "path": "/b", "value": 3},
+            {"op": "add", "path": "/c", "value": 4}
+        ]);
+
+        let err = doc.apply_patch(&patch).unwrap_err();
+
+        assert_eq!(err, PatchError::TestFailed(1, "/b".into()));
+        assert_eq!(
+            err.to_string(),
+            "Operation 1 failed, the value at `/b` is not the expected one"
+        );
+        assert_eq!(doc, json!({"a": 1, "b": 2}));
+    }
+
+    #[test]
+    fn test_merge_patch_nested() {
+        let mut doc = json!({"title": "x", "author": {"name": "fulano", "email": "f@x.com"}});
+
+        doc.apply_merge_patch(&json!({
+            "author": {"email": "f@y.com", "links": {"web": "y.com"}},
+            "tags": ["a"]
+        }));
+
+        assert_eq!(
+            doc,
+            json!({
+                "title": "x",
+                "author": {"name": "fulano", "email": "f@y.com", "links": {"web": "y.com"}},
+                "tags": ["a"]
+            })
+        );
+    }
+
+    #[test]
+    fn test_merge_patch_null_removes_key() {
+        let mut doc = json!({"a": 1, "b": {"c": 2, "d": 3}});
+
+        doc.apply_merge_patch(&json!({"a": null, "b": {"c": null}, "missing": null}));
+
+        assert_eq!(doc, json!({"b": {"d": 3}}));
+    }
+
+    #[test]
+    fn test_merge_patch_replaces_wholesale() {
+        let mut doc = json!({"a": {"b": 1}, "list": [1, 2]});
+
+        doc.apply_merge_patch(&json!({"a": "scalar", "list": [3]}));
+        assert_eq!(doc, json!({"a": "scalar", "list": [3]}));
+
+        // Objects land on non-objects by starting from an empty one
+        doc.apply_merge_patch(&json!({"a": {"b": null, "c": 1}}));
+        assert_eq!(doc, json!({"a": {"c": 1}, "list": [3]}));
+
+        doc.apply_merge_patch(&json!(true));
+        assert_eq!(doc, json!(true));
+    }
+
+    #[test]
+    fn test_invalid_patches() {
+        assert_eq!(
+            patched(json!({}), json!({"op": "add"})),
+            Err(PatchError::NotAnArray)
+        );
+        assert_eq!(
+            patched(json!({}), json!([{"op": "merge", "path": "/a"}])),
+            Err(PatchError::InvalidOperation(0, "unknown op `merge`".into()))
+        );
+        assert_eq!(
+            patched(json!({}), json!([{"path": "/a", "value": 1}])),
+            Err(PatchError::InvalidOperation(
+                0,
+                "expected a string `op` member".into()
+            ))
+        );
+        assert_eq!(
+            patched(json!({}), json!([{"op": "add", "path": "/a"}])),
+            Err(PatchError::InvalidOperation(
+                0,
+                "expected a `value` member for `add`".into()
+            ))
+        );
+        assert_eq!(
+            patched(
+                json!({"list": [1]}),
+                json!([{"op": "add", "path": "/list/2", "value": 1}])
+            ),
+            Err(PatchError::Pointer(
+                0,
+                PointerError::NotFound("/list/2".into())
+            ))
+        );
+    }
+}
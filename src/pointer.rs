@@ -0,0 +1,527 @@
+use crate::parser::JsonValue;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum PointerError {
+    #[error("Invalid JSON Pointer `{0}`, it must be empty or start with `/`")]
+    InvalidPointer(String),
+    #[error("No value found at `{0}`")]
+    NotFound(String),
+    #[error("Invalid array index at `{0}`")]
+    InvalidIndex(String),
+    #[error("Cannot look up `{0}` inside {1}")]
+    NotAContainer(String, &'static str),
+}
+
+/// Splits an RFC 6901 JSON Pointer into its unescaped reference tokens.
+/// Returns `None` when the pointer is neither empty nor starts with `/`
+fn parse_pointer(path: &str) -> Option<Vec<String>> {
+    if path.is_empty() {
+        return Some(vec![]);
+    }
+
+    let tokens = path.strip_prefix('/')?;
+
+    Some(
+        tokens
+            .split('/')
+            .map(|token| token.replace("~1", "/").replace("~0", "~"))
+            .collect(),
+    )
+}
+
+/// Escapes a key so it can be used as a reference token, the inverse of [`parse_pointer`]
+pub(crate) fn escape_token(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Parses an array index token, rejecting leading zeros and signs as RFC 6901 requires
+pub(crate) fn parse_index(token: &str) -> Option<usize> {
+    if token.is_empty() || !token.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    if token.len() > 1 && token.starts_with('0') {
+        return None;
+    }
+
+    token.parse().ok()
+}
+
+/// Follows every reference token in `path` but the last, returning the value
+/// holding the target along with the last token and the pointer to the parent
+pub(crate) fn parent_mut<'a>(
+    root: &'a mut JsonValue,
+    path: &str,
+) -> Result<(&'a mut JsonValue, String, String), PointerError> {
+    let mut tokens =
+        parse_pointer(path).ok_or_else(|| PointerError::InvalidPointer(path.to_string()))?;
+    let last = tokens
+        .pop()
+        .expect("the empty pointer is handled by the caller");
+
+    let mut target = root;
+    let mut at = String::new();
+
+    for token in tokens {
+        at = format!("{}/{}", at, escape_token(&token));
+        target = match target {
+            JsonValue::Object(obj) => obj.get_mut(&token),
+            JsonValue::Array(arr) => {
+                let i =
+                    parse_index(&token).ok_or_else(|| PointerError::InvalidIndex(at.clone()))?;
+                arr.get_mut(i)
+            }
+            other => return Err(PointerError::NotAContainer(at, other.type_name())),
+        }
+        .ok_or_else(|| PointerError::NotFound(at.clone()))?;
+    }
+
+    Ok((target, last, at))
+}
+
+/// Removes the value at `path` like [`JsonValue::remove_pointer`], saying why
+/// when it can't
+pub(crate) fn remove_at(root: &mut JsonValue, path: &str) -> Result<JsonValue, PointerError> {
+    if path.is_empty() {
+        return Err(PointerError::NotFound(String::new()));
+    }
+
+    let (parent, last, at) = parent_mut(root, path)?;
+    let at = format!("{}/{}", at, escape_token(&last));
+
+    match parent {
+        // Keeps the rest of the keys in order
+        #[cfg(not(feature = "btreemap"))]
+        JsonValue::Object(obj) => obj.shift_remove(&last),
+        #[cfg(feature = "btreemap")]
+        JsonValue::Object(obj) => obj.remove(&last),
+        JsonValue::Array(arr) => {
+            let i = parse_index(&last).ok_or_else(|| PointerError::InvalidIndex(at.clone()))?;
+            (i < arr.len()).then(|| arr.remove(i))
+        }
+        other => return Err(PointerError::NotAContainer(at, other.type_name())),
+    }
+    .ok_or(PointerError::NotFound(at))
+}
+
+/// Step of a dotted path like `user.roles[0]`
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Takes a plain key off the front of a dotted path, up to the next `.` or `[`
+fn take_key<'a>(path: &'a str, segments: &mut Vec<Segment>) -> Option<&'a str> {
+    let end = path.find(['.', '[']).unwrap_or(path.len());
+    if end == 0 {
+        return None;
+    }
+
+    segments.push(Segment::Key(path[..end].to_string()));
+    Some(&path[end..])
+}
+
+/// Takes what's between brackets off the front of a dotted path, right past
+/// the `[`. It's either an index or a double-quoted key where `\` escapes the
+/// next character
+fn take_bracket<'a>(path: &'a str, segments: &mut Vec<Segment>) -> Option<&'a str> {
+    let Some(quoted) = path.strip_prefix('"') else {
+        let end = path.find(']')?;
+        segments.push(Segment::Index(parse_index(&path[..end])?));
+        return Some(&path[end + 1..]);
+    };
+
+    let mut key = String::new();
+    let mut chars = quoted.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => key.push(chars.next()?.1),
+            '"' => {
+                segments.push(Segment::Key(key));
+                return quoted[i + 1..].strip_prefix(']');
+            }
+            c => key.push(c),
+        }
+    }
+
+    None
+}
+
+/// Splits a path like `user.roles[0]["a.b"]` into its segments. Returns `None`
+/// when it's malformed, like having empty keys or unclosed brackets
+fn parse_dotted(path: &str) -> Option<Vec<Segment>> {
+    let mut segments = Vec::new();
+    let mut rest = path;
+
+    if !rest.is_empty() && !rest.starts_with('[') {
+        rest = take_key(rest, &mut segments)?;
+    }
+
+    while let Some(c) = rest.chars().next() {
+        rest = match c {
+            '.' => take_key(&rest[1..], &mut segments)?,
+            '[' => take_bracket(&rest[1..], &mut segments)?,
+            _ => return None,
+        };
+    }
+
+    Some(segments)
+}
+
+impl JsonValue {
+    /// Resolves an RFC 6901 JSON Pointer such as `/user/roles/0`, where `~0`
+    /// and `~1` stand for `~` and `/` inside keys. The empty pointer `""`
+    /// refers to the whole document
+    pub fn pointer(&self, path: &str) -> Option<&JsonValue> {
+        let mut target = self;
+
+        for token in parse_pointer(path)? {
+            target = match target {
+                JsonValue::Object(obj) => obj.get(&token)?,
+                JsonValue::Array(arr) => arr.get(parse_index(&token)?)?,
+                _ => return None,
+            };
+        }
+
+        Some(target)
+    }
+
+    /// Replaces the value at an RFC 6901 JSON Pointer, or adds it as a new key
+    /// when its parent is an object. In an array, `-` appends past the last
+    /// element. Everything up to the parent must already exist
+    ///
+    /// ```
+    /// use crusty_json::JsonValue;
+    ///
+    /// let mut json: JsonValue = r#"{"tags": ["a"]}"#.parse().unwrap();
+    /// json.set_pointer("/tags/-", "b".into()).unwrap();
+    /// json.set_pointer("/debug", true.into()).unwrap();
+    ///
+    /// assert_eq!(json.pointer("/tags/1"), Some(&JsonValue::from("b")));
+    /// assert_eq!(json["debug"], JsonValue::Boolean(true));
+    /// ```
+    pub fn set_pointer(&mut self, path: &str, new: JsonValue) -> Result<(), PointerError> {
+        if path.is_empty() {
+            *self = new;
+            return Ok(());
+        }
+
+        let (parent, last, at) = parent_mut(self, path)?;
+        let at = format!("{}/{}", at, escape_token(&last));
+
+        match parent {
+            JsonValue::Object(obj) => {
+                obj.insert(last, new);
+            }
+            JsonValue::Array(arr) if last == "-" => arr.push(new),
+            JsonValue::Array(arr) => {
+                let i = parse_index(&last).ok_or(PointerError::InvalidIndex(at.clone()))?;
+                *arr.get_mut(i).ok_or(PointerError::NotFound(at))? = new;
+            }
+            other => return Err(PointerError::NotAContainer(at, other.type_name())),
+        }
+
+        Ok(())
+    }
+
+    /// Removes the value at an RFC 6901 JSON Pointer and returns it, shifting
+    /// down the elements after it in an array. Returns `None` if the pointer
+    /// doesn't resolve, as well as for `""` since the document itself can't go
+    pub fn remove_pointer(&mut self, path: &str) -> Option<JsonValue> {
+        remove_at(self, path).ok()
+    }
+
+    /// Resolves a dotted path such as `user.roles[0].name`, with keys that
+    /// contain dots or brackets written as `["a.b"]`. The empty path refers to
+    /// the whole document
+    pub fn get_dotted(&self, path: &str) -> Option<&JsonValue> {
+        let mut target = self;
+
+        for segment in parse_dotted(path)? {
+            target = match (target, segment) {
+                (JsonValue::Object(obj), Segment::Key(key)) => obj.get(&key)?,
+                (JsonValue::Array(arr), Segment::Index(i)) => arr.get(i)?,
+                _ => return None,
+            };
+        }
+
+        Some(target)
+    }
+
+    /// Iterates over every value in the tree, this one included, in depth-first
+    /// order along with the JSON Pointer that resolves to it
+    pub fn walk(&self) -> impl Iterator<Item = (String, &JsonValue)> {
+        Walk {
+            stack: vec![(String::new(), self)],
+        }
+    }
+}
+
+/// Depth-first iterator behind [`JsonValue::walk`], with pending values on a stack
+struct Walk<'a> {
+    stack: Vec<(String, &'a JsonValue)>,
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = (String, &'a JsonValue);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (path, value) = self.stack.pop()?;
+
+        // Children are pushed in reverse so the first one comes out next
+        match value {
+            JsonValue::Array(arr) => {
+                for (i, child) in arr.iter().enumerate().rev() {
+                    self.stack.push((format!("{}/{}", path, i), child));
+                }
+            }
+            JsonValue::Object(obj) => {
+                for (key, child) in obj.iter().rev() {
+                    self.stack
+                        .push((format!("{}/{}", path, escape_token(key)), child));
+                }
+            }
+            _ => {}
+        }
+
+        Some((path, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::from_str;
+
+    use super::{JsonValue, PointerError};
+
+    #[test]
+    fn test_pointer_nested() {
+        let json =
+            from_str(r#"{"user": {"name": "fulano", "roles": ["admin", {"level": 3}]}}"#).unwrap();
+
+        assert_eq!(
+            json.pointer("/user/name"),
+            Some(&JsonValue::String("fulano".into()))
+        );
+        assert_eq!(
+            json.pointer("/user/roles/0"),
+            Some(&JsonValue::String("admin".into()))
+        );
+        assert_eq!(
+            json.pointer("/user/roles/1/level"),
+            Some(&JsonValue::Number(3.into()))
+        );
+    }
+
+    #[test]
+    fn test_pointer_root() {
+        let json = from_str(r#"{"a": 1}"#).unwrap();
+        assert_eq!(json.pointer(""), Some(&json));
+    }
+
+    #[test]
+    fn test_pointer_tilde_escapes() {
+        let json = from_str(r#"{"a/b": 1, "m~n": 2, "~1": 3, "": 4}"#).unwrap();
+
+        assert_eq!(json.pointer("/a~1b"), Some(&JsonValue::Number(1.into())));
+        assert_eq!(json.pointer("/m~0n"), Some(&JsonValue::Number(2.into())));
+        assert_eq!(json.pointer("/~01"), Some(&JsonValue::Number(3.into())));
+        assert_eq!(json.pointer("/"), Some(&JsonValue::Number(4.into())));
+    }
+
+    #[test]
+    fn test_pointer_unresolved() {
+        let json = from_str(r#"{"roles": ["admin"]}"#).unwrap();
+
+        assert_eq!(json.pointer("roles"), None);
+        assert_eq!(json.pointer("/missing"), None);
+        assert_eq!(json.pointer("/roles/1"), None);
+        assert_eq!(json.pointer("/roles/00"), None);
+        assert_eq!(json.pointer("/roles/-"), None);
+        assert_eq!(json.pointer("/roles/0/name"), None);
+    }
+
+    #[test]
+    #[cfg(not(feature = "btreemap"))]
+    fn test_walk_paths() {
+        let json =
+            from_str(r#"{"user": {"name": "fulano", "roles": ["admin"]}, "a/b": [], "ok": true}"#)
+                .unwrap();
+
+        let paths: Vec<String> = json.walk().map(|(path, _)| path).collect();
+
+        assert_eq!(
+            paths,
+            vec![
+                "",
+                "/user",
+                "/user/name",
+                "/user/roles",
+                "/user/roles/0",
+                "/a~1b",
+                "/ok",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_walk_paths_resolve() {
+        let json = from_str(r#"{"m~n": [1, {"x": null}], "": 2}"#).unwrap();
+
+        for (path, value) in json.walk() {
+            assert_eq!(json.pointer(&path), Some(value));
+        }
+
+        assert_eq!(json.walk().count(), json.node_count());
+    }
+
+    #[test]
+    fn test_get_dotted() {
+        let json = from_str(r#"{"user": {"name": "fulano", "roles": ["admin", {"name": "dev"}]}}"#)
+            .unwrap();
+
+        assert_eq!(json.get_dotted("user.name"), Some(&json!("fulano")));
+        assert_eq!(json.get_dotted("user.roles[0]"), Some(&json!("admin")));
+        assert_eq!(json.get_dotted("user.roles[1].name"), Some(&json!("dev")));
+        assert_eq!(json.get_dotted("user"), json.pointer("/user"));
+        assert_eq!(json.get_dotted(""), Some(&json));
+    }
+
+    #[test]
+    fn test_get_dotted_bracketed_keys() {
+        let json = from_str(r#"{"a.b": {"c": [[1, 2]]}, "q\"x": 3, "[k]": 4}"#).unwrap();
+
+        assert_eq!(json.get_dotted(r#"["a.b"].c[0][1]"#), Some(&json!(2)));
+        assert_eq!(json.get_dotted(r#"["a.b"]["c"]"#), Some(&json!([[1, 2]])));
+        assert_eq!(json.get_dotted(r#"["q\"x"]"#), Some(&json!(3)));
+        assert_eq!(json.get_dotted(r#"["[k]"]"#), Some(&json!(4)));
+        assert_eq!(json.get_dotted("a.b"), None);
+    }
+
+    #[test]
+    fn test_set_pointer_replaces_scalar() {
+        let mut json = from_str(r#"{"user": {"name": "fulano", "roles": ["admin"]}}"#).unwrap();
+
+        json.set_pointer("/user/name", json!("mengano")).unwrap();
+        json.set_pointer("/user/roles/0", json!({"level": 3}))
+            .unwrap();
+
+        assert_eq!(
+            json,
+            json!({"user": {"name": "mengano", "roles": [{"level": 3}]}})
+        );
+
+        json.set_pointer("", json!(null)).unwrap();
+        assert_eq!(json, JsonValue::Null);
+    }
+
+    #[test]
+    fn test_set_pointer_adds_key() {
+        let mut json = from_str(r#"{"a": {}}"#).unwrap();
+
+        json.set_pointer("/a/b~1c", json!(1)).unwrap();
+        json.set_pointer("/d", json!([])).unwrap();
+
+        assert_eq!(json, json!({"a": {"b/c": 1}, "d": []}));
+    }
+
+    #[test]
+    fn test_set_pointer_appends_to_array() {
+        let mut json = from_str(r#"{"list": [1]}"#).unwrap();
+
+        json.set_pointer("/list/-", json!(2)).unwrap();
+        json.set_pointer("/list/-", json!([3])).unwrap();
+        json.set_pointer("/list/2/-", json!(4)).unwrap();
+
+        assert_eq!(json, json!({"list": [1, 2, [3, 4]]}));
+    }
+
+    #[test]
+    fn test_set_pointer_errors() {
+        let mut json = from_str(r#"{"a": {"b": 1}, "list": [1]}"#).unwrap();
+        let before = json.clone();
+
+        assert_eq!(
+            json.set_pointer("/missing/b", json!(1)),
+            Err(PointerError::NotFound("/missing".into()))
+        );
+        assert_eq!(
+            json.set_pointer("/a/b/c", json!(1)),
+            Err(PointerError::NotAContainer("/a/b/c".into(), "number"))
+        );
+        assert_eq!(
+            json.set_pointer("/list/1", json!(1)),
+            Err(PointerError::NotFound("/list/1".into()))
+        );
+        assert_eq!(
+            json.set_pointer("/list/01", json!(1)),
+            Err(PointerError::InvalidIndex("/list/01".into()))
+        );
+        assert_eq!(
+            json.set_pointer("/list/x/y", json!(1)),
+            Err(PointerError::InvalidIndex("/list/x".into()))
+        );
+        assert_eq!(
+            json.set_pointer("a", json!(1)),
+            Err(PointerError::InvalidPointer("a".into()))
+        );
+        assert_eq!(
+            PointerError::NotAContainer("/a/b/c".into(), "number").to_string(),
+            "Cannot look up `/a/b/c` inside number"
+        );
+        assert_eq!(json, before);
+    }
+
+    #[test]
+    fn test_remove_pointer_key() {
+        let mut json = from_str(r#"{"a": 1, "b": {"c": [2], "d": 3}, "e": 4}"#).unwrap();
+
+        assert_eq!(json.remove_pointer("/b/c"), Some(json!([2])));
+        assert_eq!(json.remove_pointer("/a"), Some(json!(1)));
+        assert_eq!(json.remove_pointer("/a"), None);
+
+        assert_eq!(json, json!({"b": {"d": 3}, "e": 4}));
+    }
+
+    #[test]
+    fn test_remove_pointer_shifts_elements() {
+        let mut json = from_str(r#"{"list": ["a", "b", "c", "d"]}"#).unwrap();
+
+        assert_eq!(json.remove_pointer("/list/1"), Some(json!("b")));
+        assert_eq!(json.pointer("/list/1"), Some(&json!("c")));
+        assert_eq!(json.remove_pointer("/list/1"), Some(json!("c")));
+
+        assert_eq!(json, json!({"list": ["a", "d"]}));
+    }
+
+    #[test]
+    fn test_remove_pointer_unresolved() {
+        let mut json = from_str(r#"{"list": [1], "n": 2}"#).unwrap();
+        let before = json.clone();
+
+        assert_eq!(json.remove_pointer("/list/1"), None);
+        assert_eq!(json.remove_pointer("/list/-"), None);
+        assert_eq!(json.remove_pointer("/missing/a"), None);
+        assert_eq!(json.remove_pointer("/n/0"), None);
+        assert_eq!(json.remove_pointer("list"), None);
+        assert_eq!(json.remove_pointer(""), None);
+        assert_eq!(json, before);
+    }
+
+    #[test]
+    fn test_get_dotted_unresolved() {
+        let json = from_str(r#"{"roles": ["admin"], "n": 1}"#).unwrap();
+
+        assert_eq!(json.get_dotted("roles[1]"), None);
+        assert_eq!(json.get_dotted("roles.0"), None);
+        assert_eq!(json.get_dotted("n[0]"), None);
+        assert_eq!(json.get_dotted("roles[01]"), None);
+        assert_eq!(json.get_dotted("roles[0"), None);
+        assert_eq!(json.get_dotted(r#"["roles"#), None);
+        assert_eq!(json.get_dotted("roles..n"), None);
+        assert_eq!(json.get_dotted("n."), None);
+        assert_eq!(json.get_dotted(".n"), None);
+        assert_eq!(json.get_dotted("roles[0]x"), None);
+    }
+}
@@ -0,0 +1,286 @@
+use crate::{
+    error::JsonError,
+    lexer::{next_token_at, JsonToken, LexError, LexerOptions, Position, Span},
+    parser::{parser_from_iter, JsonValue, ParserOptions},
+};
+use std::{
+    io::{self, Read},
+    iter, mem, str,
+};
+
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// Lexes tokens out of a reader a chunk at a time, only keeping the input
+/// that hasn't been turned into tokens yet
+struct ReaderTokens<R> {
+    reader: R,
+    buf: String,
+    /// Bytes of a character cut in half by the end of the last read
+    pending: Vec<u8>,
+    /// Bytes already dropped from the front of `buf`, so errors can point
+    /// into the input as a whole
+    dropped: usize,
+    at: Position,
+    eof: bool,
+    /// Whether the input is known to start with a byte-order mark or not
+    bom_checked: bool,
+    options: LexerOptions,
+}
+
+impl<R: Read> ReaderTokens<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: String::new(),
+            pending: Vec::new(),
+            dropped: 0,
+            at: Position {
+                offset: 0,
+                span: Span { line: 1, col: 1 },
+            },
+            eof: false,
+            bom_checked: false,
+            options: LexerOptions::default(),
+        }
+    }
+
+    /// Appends the next chunk of input, dropping the part that was already lexed.
+    /// A token still being read gets at least as much new input as it has so
+    /// far, so a long one is lexed again only a logarithmic number of times
+    fn fill(&mut self) -> io::Result<()> {
+        self.buf.drain(..self.at.offset);
+        self.dropped += self.at.offset;
+        self.at.offset = 0;
+
+        let unfinished = self.buf.len();
+        let mut chunk = mem::take(&mut self.pending);
+        let len = chunk.len();
+        chunk.resize(len + CHUNK_SIZE.max(unfinished), 0);
+
+        let mut end = len;
+        loop {
+            match self.reader.read(&mut chunk[end..]) {
+                Ok(0) => {
+                    self.eof = true;
+                    break;
+                }
+                Ok(read) => end += read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+            if end - len >= unfinished {
+                break;
+            }
+        }
+        chunk.truncate(end);
+
+        match str::from_utf8(&chunk) {
+            Ok(text) => self.buf.push_str(text),
+            // A character split between reads gets completed by the next one
+            Err(err) if err.error_len().is_none() && !self.eof => {
+                let (valid, rest) = chunk.split_at(err.valid_up_to());
+                self.buf
+                    .push_str(str::from_utf8(valid).expect("checked to be valid"));
+                self.pending = rest.to_vec();
+            }
+            Err(err) => return Err(io::Error::new(io::ErrorKind::InvalidData, err)),
+        }
+
+        // A UTF-8 byte-order mark is only meaningful at the very start of the input
+        if !self.bom_checked && !self.buf.is_empty() {
+            if self.buf.starts_with('\u{FEFF}') {
+                self.buf.drain(..'\u{FEFF}'.len_utf8());
+                self.dropped += '\u{FEFF}'.len_utf8();
+            }
+            self.bom_checked = true;
+        }
+
+        Ok(())
+    }
+
+    /// Moves the offset of an error found in `buf` to where it is in the input
+    fn lex_error(&self, err: LexError) -> JsonError {
+        LexError {
+            offset: self.dropped + err.offset,
+            ..err
+        }
+        .into()
+    }
+
+    fn next_token(&mut self) -> Result<Option<(JsonToken, Span)>, JsonError> {
+        loop {
+            match next_token_at(&self.buf, self.at, &self.options) {
+                // Tokens running into the end of the buffer might go on in the next chunk
+                Ok(Some((token, span, end))) if end.offset < self.buf.len() || self.eof => {
+                    let token = token.into_owned();
+                    self.at = end;
+                    self.bom_checked = true;
+                    return Ok(Some((token, span)));
+                }
+                Ok(None) if self.eof => return Ok(None),
+                Err((err, _)) if self.eof => return Err(self.lex_error(err)),
+                // An error that ran into the end of the buffer, like an unterminated
+                // string or an escape cut in half, might go away with more input
+                Err((_, stop)) if stop == self.buf.len() => {}
+                Err((err, _)) => return Err(self.lex_error(err)),
+                Ok(_) => {}
+            }
+
+            self.fill()?;
+        }
+    }
+}
+
+/// Lexes and parses JSON read from `reader` a chunk at a time, so the text
+/// itself is never fully held in memory. There's no need to wrap the reader
+/// in a `BufReader`
+pub fn parse_reader(reader: impl Read) -> Result<JsonValue, JsonError> {
+    let mut tokens = ReaderTokens::new(reader);
+    let mut read_error = None;
+
+    let json = parser_from_iter(
+        iter::from_fn(|| match tokens.next_token() {
+            Ok(token) => token,
+            Err(err) => {
+                read_error = Some(err);
+                None
+            }
+        }),
+        &ParserOptions::default(),
+    );
+
+    if let Some(err) = read_error {
+        return Err(err);
+    }
+
+    Ok(json?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_reader, CHUNK_SIZE};
+    use crate::{error::JsonError, lexer::LexError, parser::JsonValue};
+    use std::io::{self, Cursor, Read};
+
+    /// Hands out a single byte per read, splitting every token and character
+    struct OneByte<'a>(&'a [u8]);
+
+    impl Read for OneByte<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.split_first() {
+                Some((&b, rest)) if !buf.is_empty() => {
+                    buf[0] = b;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    const INPUT: &str = "\u{FEFF}{\n  \"name\": \"Mañana \\\"quizás\\\" ☕\",\n  \"nums\": [1, -2.5e3, 0],\n  \"ok\": true, \"none\": null\n}\n";
+
+    #[test]
+    fn test_cursor_matches_from_str() {
+        let expected: JsonValue = INPUT.parse().unwrap();
+
+        assert_eq!(
+            parse_reader(Cursor::new(INPUT.as_bytes())).unwrap(),
+            expected
+        );
+        assert_eq!(parse_reader(OneByte(INPUT.as_bytes())).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_large_input_spanning_chunks() {
+        let records: Vec<String> = (0..5_000)
+            .map(|i| {
+                format!(
+                    r#"{{"id": {}, "name": "fulano {}", "tags": ["a", null]}}"#,
+                    i, i
+                )
+            })
+            .collect();
+        let input = format!("[{}]", records.join(",\n"));
+
+        let expected: JsonValue = input.parse().unwrap();
+        assert_eq!(parse_reader(input.as_bytes()).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_errors_match_from_str() {
+        for input in [
+            "[1, tru]",
+            "[1, true",
+            "{\"a\": \"unterminated}",
+            "[\"bad \\q escape\"]",
+            "[\"\\uD83D\\u0041\"]",
+            "[\n  1,\n  2e\n]",
+            "[1] [2]",
+            "\u{FEFF}[1, x]",
+            "",
+        ] {
+            let expected = input.parse::<JsonValue>().unwrap_err();
+
+            assert_eq!(parse_reader(input.as_bytes()).unwrap_err(), expected);
+            assert_eq!(
+                parse_reader(OneByte(input.as_bytes())).unwrap_err(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_error_offset_past_first_chunk() {
+        let input = format!("[{}\"\\q\"]", "1, ".repeat(CHUNK_SIZE));
+
+        let err = parse_reader(input.as_bytes()).unwrap_err();
+        assert_eq!(err, input.parse::<JsonValue>().unwrap_err());
+        assert!(matches!(
+            err,
+            JsonError::Token(LexError { offset, .. }) if offset == 3 * CHUNK_SIZE + 3
+        ));
+    }
+
+    #[test]
+    fn test_long_string_spanning_chunks() {
+        /// Counts the reads it takes to get through the input
+        struct Counted<'a>(&'a [u8], usize);
+
+        impl Read for Counted<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.1 += 1;
+                self.0.read(buf)
+            }
+        }
+
+        let text = "fulano ☕ ".repeat(6 * CHUNK_SIZE);
+        let input = format!("[\"{}\", 1]", text);
+
+        let mut reader = Counted(input.as_bytes(), 0);
+        let json = parse_reader(&mut reader).unwrap();
+        assert_eq!(json[0], JsonValue::String(text));
+
+        // Growing the reads keeps the string from being lexed once per chunk
+        assert!(reader.1 < 16, "{} reads", reader.1);
+
+        let json = parse_reader(OneByte(input.as_bytes())).unwrap();
+        assert_eq!(json, input.parse::<JsonValue>().unwrap());
+    }
+
+    #[test]
+    fn test_escapes_split_between_reads() {
+        // Reads land right after `\uD83D\`, before the low surrogate shows up
+        let input = r#"["\uD83D\uDE00", "\u00e9\n"]"#;
+        let expected: JsonValue = input.parse().unwrap();
+
+        assert_eq!(parse_reader(OneByte(input.as_bytes())).unwrap(), expected);
+        assert_eq!(expected[0], JsonValue::String("😀".into()));
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let err = parse_reader(&b"[\"\xFF\"]"[..]).unwrap_err();
+        assert!(matches!(err, JsonError::Io(err) if err.kind() == io::ErrorKind::InvalidData));
+    }
+}
@@ -0,0 +1,428 @@
+use crate::{
+    lexer::{JsonToken, Span},
+    number::JsonNumber,
+    parser::{BracketContext, JsonObject, JsonParseError, JsonValue, DEFAULT_MAX_DEPTH},
+};
+
+/// Parser that writes down errors and keeps going instead of bailing out
+struct Recover<'a> {
+    tokens: &'a [(JsonToken, Span)],
+    pos: usize,
+    errors: Vec<JsonParseError>,
+    /// How many containers the next value is nested in
+    depth: usize,
+}
+
+impl<'a> Recover<'a> {
+    fn peek(&self) -> Option<&'a JsonToken> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    /// Span of the next token, or of the last one once they run out
+    fn span(&self) -> Span {
+        match self.tokens.get(self.pos).or(self.tokens.last()) {
+            Some((_, span)) => *span,
+            None => Span { line: 1, col: 1 },
+        }
+    }
+
+    fn bump(&mut self) {
+        self.pos += 1;
+    }
+
+    /// Skips the container opened by the next token along with everything in
+    /// it, up to its closing bracket or the end of the tokens
+    fn skip_container(&mut self) {
+        let mut open = 0;
+
+        while let Some(token) = self.peek() {
+            self.bump();
+            match token {
+                JsonToken::OpenCurlyBracket | JsonToken::OpenSquareBracket => open += 1,
+                JsonToken::CloseCurlyBracket | JsonToken::CloseSquareBracket => {
+                    open -= 1;
+                    if open == 0 {
+                        return;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Parses a value, putting `null` in place of anything that isn't one
+    fn value(&mut self, context: BracketContext) -> JsonValue {
+        let span = self.span();
+
+        let value = match self.peek() {
+            Some(JsonToken::String(json_string)) => JsonValue::String(json_string.clone()),
+            Some(JsonToken::Number(json_number)) => match JsonNumber::from_token(json_number) {
+                Some(number) => JsonValue::Number(number),
+                None => {
+                    self.errors.push(JsonParseError::InvalidNumberValue(
+                        json_number.clone(),
+                        span,
+                    ));
+                    JsonValue::Null
+                }
+            },
+            Some(JsonToken::True) => JsonValue::Boolean(true),
+            Some(JsonToken::False) => JsonValue::Boolean(false),
+            Some(JsonToken::Null) => JsonValue::Null,
+            // Containers recurse, so past the limit they're dropped whole
+            // rather than risking the stack
+            Some(JsonToken::OpenCurlyBracket | JsonToken::OpenSquareBracket)
+                if self.depth >= DEFAULT_MAX_DEPTH =>
+            {
+                self.errors
+                    .push(JsonParseError::MaxDepthExceeded(DEFAULT_MAX_DEPTH, span));
+                self.skip_container();
+                return JsonValue::Null;
+            }
+            Some(token @ (JsonToken::OpenCurlyBracket | JsonToken::OpenSquareBracket)) => {
+                self.depth += 1;
+                let value = match token {
+                    JsonToken::OpenCurlyBracket => self.object(),
+                    _ => self.array(),
+                };
+                self.depth -= 1;
+                return value;
+            }
+            Some(token) => {
+                match context.check(token, span) {
+                    // Stray punctuation and bare words go away, while commas and the
+                    // bracket closing this value's container are left for it to handle
+                    Err(err) => {
+                        self.errors.push(err);
+                        self.bump();
+                    }
+                    Ok(()) if matches!(token, JsonToken::Colon | JsonToken::Identifier(_)) => {
+                        self.errors
+                            .push(JsonParseError::InvalidValue(Some(token.clone()), span));
+                        self.bump();
+                    }
+                    Ok(()) => {
+                        self.errors
+                            .push(JsonParseError::InvalidValue(Some(token.clone()), span));
+                    }
+                }
+                return JsonValue::Null;
+            }
+            None => {
+                self.errors.push(JsonParseError::InvalidValue(None, span));
+                return JsonValue::Null;
+            }
+        };
+
+        self.bump();
+        value
+    }
+
+    fn object(&mut self) -> JsonValue {
+        self.bump();
+
+        let mut obj = JsonObject::new();
+        let mut comma_after_value: Option<Span> = None;
+
+        loop {
+            let span = self.span();
+
+            let key =
+                match self.peek() {
+                    Some(JsonToken::CloseCurlyBracket) => {
+                        self.bump();
+                        if let Some(comma_span) = comma_after_value {
+                            self.errors.push(JsonParseError::TrailingComma(comma_span));
+                        }
+                        return JsonValue::Object(obj);
+                    }
+                    Some(JsonToken::String(json_string) | JsonToken::Identifier(json_string)) => {
+                        self.bump();
+                        json_string.clone()
+                    }
+                    Some(token) => {
+                        let err = BracketContext::Object.check(token, span).err();
+                        self.errors.push(err.unwrap_or_else(|| {
+                            JsonParseError::ExpectedObjectKey(token.clone(), span)
+                        }));
+                        self.bump();
+                        continue;
+                    }
+                    None => {
+                        self.errors.push(JsonParseError::ExpectedEndOfObject(span));
+                        return JsonValue::Object(obj);
+                    }
+                };
+            comma_after_value = None;
+
+            // A missing colon is assumed to be right where it should have been
+            match self.peek() {
+                Some(JsonToken::Colon) => self.bump(),
+                token => self.errors.push(JsonParseError::ExpectedColonAfterKey(
+                    token.cloned(),
+                    self.span(),
+                )),
+            }
+
+            let value = self.value(BracketContext::Object);
+            obj.insert(key, value);
+
+            let span = self.span();
+            match self.peek() {
+                Some(JsonToken::Comma) => {
+                    self.bump();
+                    comma_after_value = Some(span);
+                }
+                Some(JsonToken::CloseCurlyBracket) => {}
+                Some(token) => {
+                    self.errors.push(JsonParseError::ExpectedCommaOrEndOfObject(
+                        Some(token.clone()),
+                        span,
+                    ));
+                    // A key right after means only the comma is missing
+                    if !matches!(token, JsonToken::String(_)) {
+                        self.bump();
+                    }
+                }
+                None => {
+                    self.errors
+                        .push(JsonParseError::ExpectedCommaOrEndOfObject(None, span));
+                    return JsonValue::Object(obj);
+                }
+            }
+        }
+    }
+
+    fn array(&mut self) -> JsonValue {
+        self.bump();
+
+        let mut arr = Vec::new();
+        let mut comma_after_value: Option<Span> = None;
+
+        loop {
+            let span = self.span();
+
+            match self.peek() {
+                Some(JsonToken::CloseSquareBracket) => {
+                    self.bump();
+                    if let Some(comma_span) = comma_after_value {
+                        self.errors.push(JsonParseError::TrailingComma(comma_span));
+                    }
+                    return JsonValue::Array(arr);
+                }
+                Some(_) => {}
+                None => {
+                    self.errors.push(JsonParseError::ExpectedEndOfArray(span));
+                    return JsonValue::Array(arr);
+                }
+            }
+            comma_after_value = None;
+
+            arr.push(self.value(BracketContext::Array));
+
+            let span = self.span();
+            match self.peek() {
+                Some(JsonToken::Comma) => {
+                    self.bump();
+                    comma_after_value = Some(span);
+                }
+                Some(JsonToken::CloseSquareBracket) => {}
+                Some(token) => {
+                    self.errors.push(JsonParseError::ExpectedCommaOrEndOfArray(
+                        Some(token.clone()),
+                        span,
+                    ));
+                    // Anything that could start a value means only the comma is missing
+                    if matches!(token, JsonToken::Colon | JsonToken::CloseCurlyBracket) {
+                        self.bump();
+                    }
+                }
+                None => {
+                    self.errors
+                        .push(JsonParseError::ExpectedCommaOrEndOfArray(None, span));
+                    return JsonValue::Array(arr);
+                }
+            }
+        }
+    }
+}
+
+/// Parses as much of the tokens as possible, putting `null` wherever a value
+/// is broken or missing and collecting every error found along the way. The
+/// tree is `None` only when there is no root object or array to build at all
+pub fn parse_recover(tokens: &[(JsonToken, Span)]) -> (Option<JsonValue>, Vec<JsonParseError>) {
+    let mut recover = Recover {
+        tokens,
+        pos: 0,
+        errors: Vec::new(),
+        depth: 0,
+    };
+
+    let root = match recover.peek() {
+        Some(JsonToken::OpenCurlyBracket | JsonToken::OpenSquareBracket) => {
+            recover.value(BracketContext::Root)
+        }
+        Some(token) => {
+            let span = recover.span();
+            let err = BracketContext::Root.check(token, span).err();
+            recover.errors.push(err.unwrap_or_else(|| {
+                JsonParseError::ExpectedObjectOrArrayAsRoot(token.clone(), span)
+            }));
+            return (None, recover.errors);
+        }
+        None => return (None, vec![JsonParseError::NoTokens]),
+    };
+
+    if let Some(token) = recover.peek() {
+        let span = recover.span();
+        let err = BracketContext::Root.check(token, span).err();
+        recover
+            .errors
+            .push(err.unwrap_or_else(|| JsonParseError::TrailingTokens(token.clone(), span)));
+    }
+
+    (Some(root), recover.errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_recover;
+    use crate::{
+        lexer::{lexer, lexer_with_options, JsonToken, LexerOptions, Span},
+        parser::{BracketContext, JsonParseError, JsonValue, DEFAULT_MAX_DEPTH},
+    };
+
+    fn recover(input: &str) -> (Option<JsonValue>, Vec<JsonParseError>) {
+        parse_recover(&lexer(input).unwrap())
+    }
+
+    #[test]
+    fn test_missing_colon() {
+        let (json, errors) = recover(r#"{"a" 1, "b": [true]}"#);
+
+        assert_eq!(json, Some(json!({"a": 1, "b": [true]})));
+        assert_eq!(
+            errors,
+            vec![JsonParseError::ExpectedColonAfterKey(
+                Some(JsonToken::Number("1".into())),
+                Span { line: 1, col: 6 }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_valid_input() {
+        let (json, errors) = recover(r#"{"a": [1, {"b": null}]}"#);
+
+        assert_eq!(json, Some(json!({"a": [1, {"b": null}]})));
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn test_placeholders_for_broken_values() {
+        let (json, errors) = recover(r#"{"a": , "b": [1 2, ], "c": }"#);
+
+        assert_eq!(json, Some(json!({"a": null, "b": [1, 2], "c": null})));
+        assert_eq!(errors.len(), 4);
+        assert!(matches!(
+            errors[0],
+            JsonParseError::InvalidValue(Some(JsonToken::Comma), _)
+        ));
+        assert!(matches!(
+            errors[1],
+            JsonParseError::ExpectedCommaOrEndOfArray(Some(JsonToken::Number(_)), _)
+        ));
+        assert!(matches!(errors[2], JsonParseError::TrailingComma(_)));
+        assert!(matches!(
+            errors[3],
+            JsonParseError::InvalidValue(Some(JsonToken::CloseCurlyBracket), _)
+        ));
+    }
+
+    #[test]
+    fn test_unclosed_containers() {
+        let (json, errors) = recover(r#"{"a": [1, 2"#);
+
+        assert_eq!(json, Some(json!({"a": [1, 2]})));
+        assert_eq!(
+            errors,
+            vec![
+                JsonParseError::ExpectedCommaOrEndOfArray(None, Span { line: 1, col: 11 }),
+                JsonParseError::ExpectedCommaOrEndOfObject(None, Span { line: 1, col: 11 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stray_closing_bracket() {
+        let (json, errors) = recover(r#"[1, }, 2]"#);
+
+        assert_eq!(json, Some(json!([1, null, 2])));
+        assert_eq!(
+            errors,
+            vec![JsonParseError::UnexpectedClosingBracket(
+                '}',
+                BracketContext::Array,
+                Span { line: 1, col: 5 }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_identifiers_as_values() {
+        let options = LexerOptions {
+            allow_unquoted_keys: true,
+            ..Default::default()
+        };
+        let recover = |input| parse_recover(&lexer_with_options(input, &options).unwrap());
+
+        let (json, errors) = recover("[a]");
+        assert_eq!(json, Some(json!([null])));
+        assert_eq!(
+            errors,
+            vec![JsonParseError::InvalidValue(
+                Some(JsonToken::Identifier("a".into())),
+                Span { line: 1, col: 2 }
+            )]
+        );
+
+        let (json, errors) = recover("{k: v, n: [1 x]}");
+        assert_eq!(json, Some(json!({"k": null, "n": [1, null]})));
+        assert_eq!(errors.len(), 3);
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        const DEPTH: usize = 100_000;
+
+        let input = format!("[{}{}, 1]", "[".repeat(DEPTH), "]".repeat(DEPTH));
+        let (json, errors) = recover(&input);
+        let json = json.unwrap();
+
+        // The part past the limit becomes `null` and the rest is still there
+        assert_eq!(
+            errors,
+            vec![JsonParseError::MaxDepthExceeded(
+                DEFAULT_MAX_DEPTH,
+                Span {
+                    line: 1,
+                    col: DEFAULT_MAX_DEPTH + 1
+                }
+            )]
+        );
+        assert_eq!(json.depth(), DEFAULT_MAX_DEPTH + 1);
+        assert_eq!(json[1], JsonValue::from(1));
+    }
+
+    #[test]
+    fn test_no_tree() {
+        assert_eq!(recover(""), (None, vec![JsonParseError::NoTokens]));
+
+        let (json, errors) = recover("1");
+        assert_eq!(json, None);
+        assert!(matches!(
+            errors[..],
+            [JsonParseError::ExpectedObjectOrArrayAsRoot(_, _)]
+        ));
+    }
+}
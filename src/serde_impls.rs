@@ -0,0 +1,150 @@
+use crate::{
+    number::JsonNumber,
+    parser::{JsonObject, JsonValue},
+};
+use serde::{
+    de::{Deserialize, Deserializer, MapAccess, SeqAccess, Visitor},
+    ser::{Serialize, SerializeMap, SerializeSeq, Serializer},
+};
+use std::fmt;
+
+impl Serialize for JsonValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            JsonValue::String(json_string) => serializer.serialize_str(json_string),
+            JsonValue::Number(json_number) => match *json_number {
+                JsonNumber::Int(n) => serializer.serialize_i64(n),
+                JsonNumber::UInt(n) => serializer.serialize_u64(n),
+                JsonNumber::Float(n) => serializer.serialize_f64(n),
+                JsonNumber::Raw(ref text) => match JsonNumber::from_token(text) {
+                    Some(number) => JsonValue::Number(number).serialize(serializer),
+                    None => serializer.serialize_f64(f64::NAN),
+                },
+                #[cfg(feature = "rust_decimal")]
+                JsonNumber::Decimal(_) => serializer.serialize_f64(json_number.as_f64()),
+            },
+            JsonValue::Boolean(json_boolean) => serializer.serialize_bool(*json_boolean),
+            JsonValue::Null => serializer.serialize_unit(),
+            JsonValue::Array(arr) => {
+                let mut seq = serializer.serialize_seq(Some(arr.len()))?;
+                for value in arr {
+                    seq.serialize_element(value)?;
+                }
+                seq.end()
+            }
+            JsonValue::Object(obj) => {
+                let mut map = serializer.serialize_map(Some(obj.len()))?;
+                for (key, value) in obj {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+struct JsonValueVisitor;
+
+impl<'de> Visitor<'de> for JsonValueVisitor {
+    type Value = JsonValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any valid JSON value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<JsonValue, E> {
+        Ok(JsonValue::Boolean(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<JsonValue, E> {
+        Ok(JsonValue::Number(v.into()))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<JsonValue, E> {
+        Ok(JsonValue::Number(v.into()))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<JsonValue, E> {
+        Ok(JsonValue::Number(v.into()))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<JsonValue, E> {
+        Ok(JsonValue::String(v.to_string()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<JsonValue, E> {
+        Ok(JsonValue::String(v))
+    }
+
+    fn visit_unit<E>(self) -> Result<JsonValue, E> {
+        Ok(JsonValue::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<JsonValue, E> {
+        Ok(JsonValue::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<JsonValue, D::Error> {
+        Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<JsonValue, A::Error> {
+        let mut arr = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+
+        while let Some(value) = seq.next_element()? {
+            arr.push(value);
+        }
+
+        Ok(JsonValue::Array(arr))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<JsonValue, A::Error> {
+        let mut obj = JsonObject::new();
+
+        // Keys are kept in the order the source yields them; a repeated key
+        // keeps its first position and takes the last value
+        while let Some((key, value)) = map.next_entry()? {
+            obj.insert(key, value);
+        }
+
+        Ok(JsonValue::Object(obj))
+    }
+}
+
+impl<'de> Deserialize<'de> for JsonValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<JsonValue, D::Error> {
+        deserializer.deserialize_any(JsonValueVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{from_str, JsonValue};
+
+    #[test]
+    fn test_serialize_with_serde_json() {
+        let json = from_str(r#"{"name": "fulano", "nested": {"tags": [1, true, null]}}"#).unwrap();
+
+        assert_eq!(
+            serde_json::to_string(&json).unwrap(),
+            r#"{"name":"fulano","nested":{"tags":[1,true,null]}}"#
+        );
+    }
+
+    #[test]
+    fn test_deserialize_with_serde_json() {
+        let json: JsonValue = serde_json::from_str(r#"{"a": [1, 2.5, -3], "b": null}"#).unwrap();
+        assert_eq!(json, from_str(r#"{"a": [1, 2.5, -3], "b": null}"#).unwrap());
+    }
+
+    #[test]
+    fn test_serde_json_round_trip() {
+        let json =
+            from_str(r#"{"name": "fulano", "nested": {"tags": [1, true, null, "x"]}}"#).unwrap();
+
+        let text = serde_json::to_string(&json).unwrap();
+        let round_tripped: JsonValue = serde_json::from_str(&text).unwrap();
+
+        assert_eq!(round_tripped, json);
+    }
+}
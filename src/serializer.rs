@@ -0,0 +1,649 @@
+use crate::parser::JsonValue;
+use std::{
+    fmt::{self, Write},
+    io,
+};
+
+/// Optional escapes in strings, on top of the ones JSON always needs
+#[derive(Debug, Default, Clone, Copy)]
+struct Escapes {
+    ascii_only: bool,
+    slashes: bool,
+}
+
+fn write_string(f: &mut impl Write, s: &str, escapes: Escapes) -> fmt::Result {
+    f.write_char('"')?;
+
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            '\u{0008}' => f.write_str("\\b")?,
+            '\u{000C}' => f.write_str("\\f")?,
+            '\u{0000}'..='\u{001F}' => write!(f, "\\u{:04x}", c as u32)?,
+            '/' if escapes.slashes => f.write_str("\\/")?,
+            // Characters outside the BMP take a surrogate pair
+            _ if escapes.ascii_only && !c.is_ascii() => {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    write!(f, "\\u{:04x}", unit)?;
+                }
+            }
+            _ => f.write_char(c)?,
+        }
+    }
+
+    f.write_char('"')
+}
+
+// ANSI escape codes used by the colored pretty output
+const KEY_COLOR: &str = "\x1b[1;34m";
+const STRING_COLOR: &str = "\x1b[32m";
+const NUMBER_COLOR: &str = "\x1b[33m";
+const LITERAL_COLOR: &str = "\x1b[35m";
+const RESET_COLOR: &str = "\x1b[0m";
+
+fn write_indent(f: &mut impl Write, indent: usize, level: usize) -> fmt::Result {
+    for _ in 0..indent * level {
+        f.write_char(' ')?;
+    }
+
+    Ok(())
+}
+
+fn write_pretty(
+    f: &mut impl Write,
+    value: &JsonValue,
+    indent: usize,
+    level: usize,
+    colored: bool,
+    escapes: Escapes,
+) -> fmt::Result {
+    match value {
+        JsonValue::Array(arr) if !arr.is_empty() => {
+            f.write_str("[\n")?;
+
+            for (i, value) in arr.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(",\n")?;
+                }
+                write_indent(f, indent, level + 1)?;
+                write_pretty(f, value, indent, level + 1, colored, escapes)?;
+            }
+
+            f.write_char('\n')?;
+            write_indent(f, indent, level)?;
+            f.write_char(']')
+        }
+        JsonValue::Object(obj) if !obj.is_empty() => {
+            f.write_str("{\n")?;
+
+            for (i, (key, value)) in obj.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(",\n")?;
+                }
+                write_indent(f, indent, level + 1)?;
+                if colored {
+                    f.write_str(KEY_COLOR)?;
+                    write_string(f, key, escapes)?;
+                    f.write_str(RESET_COLOR)?;
+                } else {
+                    write_string(f, key, escapes)?;
+                }
+                f.write_str(": ")?;
+                write_pretty(f, value, indent, level + 1, colored, escapes)?;
+            }
+
+            f.write_char('\n')?;
+            write_indent(f, indent, level)?;
+            f.write_char('}')
+        }
+        // Scalars and empty containers look the same as in compact output
+        JsonValue::Array(_) | JsonValue::Object(_) => write_compact(f, value, escapes),
+        _ if colored => {
+            let color = match value {
+                JsonValue::String(_) => STRING_COLOR,
+                JsonValue::Number(_) => NUMBER_COLOR,
+                _ => LITERAL_COLOR,
+            };
+            f.write_str(color)?;
+            write_compact(f, value, escapes)?;
+            f.write_str(RESET_COLOR)
+        }
+        _ => write_compact(f, value, escapes),
+    }
+}
+
+fn write_compact(f: &mut impl Write, value: &JsonValue, escapes: Escapes) -> fmt::Result {
+    match value {
+        JsonValue::String(json_string) => write_string(f, json_string, escapes),
+        JsonValue::Number(json_number) => write!(f, "{}", json_number),
+        JsonValue::Boolean(json_boolean) => write!(f, "{}", json_boolean),
+        JsonValue::Null => f.write_str("null"),
+        JsonValue::Array(arr) => {
+            f.write_char('[')?;
+
+            for (i, value) in arr.iter().enumerate() {
+                if i > 0 {
+                    f.write_char(',')?;
+                }
+                write_compact(f, value, escapes)?;
+            }
+
+            f.write_char(']')
+        }
+        JsonValue::Object(obj) => {
+            f.write_char('{')?;
+
+            for (i, (key, value)) in obj.iter().enumerate() {
+                if i > 0 {
+                    f.write_char(',')?;
+                }
+                write_string(f, key, escapes)?;
+                f.write_char(':')?;
+                write_compact(f, value, escapes)?;
+            }
+
+            f.write_char('}')
+        }
+    }
+}
+
+/// Writes a number the way ECMAScript's `Number.prototype.toString` does, as
+/// required by JSON canonicalization. Non-finite values have no canonical form
+/// and are written as `null` like everywhere else
+fn write_es_number(f: &mut impl Write, n: f64) -> fmt::Result {
+    if !n.is_finite() {
+        return f.write_str("null");
+    }
+    if n == 0.0 {
+        // Covers `-0` too
+        return f.write_char('0');
+    }
+    if n < 0.0 {
+        f.write_char('-')?;
+    }
+
+    // The shortest digits that round-trip, as in `d.ddde<exp>`
+    let formatted = format!("{:e}", n.abs());
+    let (mantissa, exp) = formatted.split_once('e').expect("exponent format");
+    let digits = mantissa.replace('.', "");
+    let k = digits.len() as i32;
+    // Position of the decimal point relative to the start of the digits
+    let point = exp.parse::<i32>().expect("exponent format") + 1;
+
+    if k <= point && point <= 21 {
+        f.write_str(&digits)?;
+        write_zeros(f, point - k)
+    } else if 0 < point && point <= 21 {
+        let (int, frac) = digits.split_at(point as usize);
+        write!(f, "{}.{}", int, frac)
+    } else if -6 < point && point <= 0 {
+        f.write_str("0.")?;
+        write_zeros(f, -point)?;
+        f.write_str(&digits)
+    } else {
+        let (first, rest) = digits.split_at(1);
+        f.write_str(first)?;
+        if !rest.is_empty() {
+            write!(f, ".{}", rest)?;
+        }
+        write!(
+            f,
+            "e{}{}",
+            if point > 0 { "+" } else { "-" },
+            (point - 1).abs()
+        )
+    }
+}
+
+fn write_zeros(f: &mut impl Write, count: i32) -> fmt::Result {
+    for _ in 0..count {
+        f.write_char('0')?;
+    }
+
+    Ok(())
+}
+
+fn write_canonical(f: &mut impl Write, value: &JsonValue) -> fmt::Result {
+    match value {
+        JsonValue::Number(json_number) => write_es_number(f, json_number.as_f64()),
+        JsonValue::Array(arr) => {
+            f.write_char('[')?;
+
+            for (i, value) in arr.iter().enumerate() {
+                if i > 0 {
+                    f.write_char(',')?;
+                }
+                write_canonical(f, value)?;
+            }
+
+            f.write_char(']')
+        }
+        JsonValue::Object(obj) => {
+            // Keys are ordered by their UTF-16 code units, not by code points
+            let mut members: Vec<_> = obj.iter().collect();
+            members.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+
+            f.write_char('{')?;
+
+            for (i, (key, value)) in members.into_iter().enumerate() {
+                if i > 0 {
+                    f.write_char(',')?;
+                }
+                write_string(f, key, Escapes::default())?;
+                f.write_char(':')?;
+                write_canonical(f, value)?;
+            }
+
+            f.write_char('}')
+        }
+        // Strings and literals are already written the canonical way
+        _ => write!(f, "{}", value),
+    }
+}
+
+/// How [`write_json`] lays out its output
+#[derive(Debug, Default, Clone)]
+pub struct FormatOptions {
+    /// Spaces per nesting level for multi-line output, or compact output if `None`
+    pub indent: Option<usize>,
+    /// Highlight keys and scalar values through ANSI color codes, only applied
+    /// to multi-line output
+    pub color: bool,
+    /// Escape every non-ASCII character as `\uXXXX`, for output that survives
+    /// transports that aren't UTF-8 clean
+    pub ascii_only: bool,
+    /// Escape `/` as `\/`, so no `</script>` can show up when embedding the
+    /// output in HTML
+    pub escape_slashes: bool,
+}
+
+/// Lets the `fmt::Write` based serializer write straight into an `io::Write`,
+/// keeping hold of the I/O error that `fmt::Error` has no room for
+struct IoAdapter<'a, W> {
+    inner: &'a mut W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> Write for IoAdapter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
+}
+
+/// Serializes `value` into `out` as laid out by `options`, without building
+/// the whole text in memory first
+pub fn write_json(
+    value: &JsonValue,
+    out: &mut impl io::Write,
+    options: &FormatOptions,
+) -> io::Result<()> {
+    let mut adapter = IoAdapter {
+        inner: out,
+        error: None,
+    };
+
+    let escapes = Escapes {
+        ascii_only: options.ascii_only,
+        slashes: options.escape_slashes,
+    };
+    let result = match options.indent {
+        Some(indent) => write_pretty(&mut adapter, value, indent, 0, options.color, escapes),
+        None => write_compact(&mut adapter, value, escapes),
+    };
+
+    match (result, adapter.error) {
+        (Ok(()), _) => Ok(()),
+        (Err(_), Some(err)) => Err(err),
+        (Err(_), None) => Err(io::Error::other("formatter error")),
+    }
+}
+
+impl JsonValue {
+    /// Serializes the value as multi-line JSON, indenting each nesting level
+    /// by `indent` spaces
+    pub fn to_pretty_string(&self, indent: usize) -> String {
+        let mut out = String::new();
+        write_pretty(&mut out, self, indent, 0, false, Escapes::default())
+            .expect("writing to a String never fails");
+        out
+    }
+
+    /// Same as [`JsonValue::to_pretty_string`], but writes into `w` bit by bit
+    /// instead of building the whole text in memory first
+    pub fn to_writer_pretty(&self, w: &mut impl io::Write, indent: usize) -> io::Result<()> {
+        let options = FormatOptions {
+            indent: Some(indent),
+            ..Default::default()
+        };
+        write_json(self, w, &options)
+    }
+
+    /// Same as [`JsonValue::to_pretty_string`], with keys and scalar values
+    /// highlighted through ANSI color codes for terminal output
+    pub fn to_colored_pretty_string(&self, indent: usize) -> String {
+        let mut out = String::new();
+        write_pretty(&mut out, self, indent, 0, true, Escapes::default())
+            .expect("writing to a String never fails");
+        out
+    }
+}
+
+impl JsonValue {
+    /// Serializes the value as canonical JSON following RFC 8785 (JCS), so
+    /// equal documents always serialize to the same bytes no matter their key
+    /// order or how their numbers were written. Numbers are treated as `f64`
+    pub fn to_canonical_string(&self) -> String {
+        let mut out = String::new();
+        write_canonical(&mut out, self).expect("writing to a String never fails");
+        out
+    }
+}
+
+impl fmt::Display for JsonValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_compact(f, self, Escapes::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lexer::lexer,
+        parser::{parser, JsonObject},
+    };
+
+    use super::{write_json, FormatOptions, JsonValue};
+
+    fn parse(input: &str) -> JsonValue {
+        parser(&lexer(input).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_display_scalars() {
+        assert_eq!(JsonValue::Null.to_string(), "null");
+        assert_eq!(JsonValue::Boolean(true).to_string(), "true");
+        assert_eq!(JsonValue::Boolean(false).to_string(), "false");
+        assert_eq!(JsonValue::Number(20.into()).to_string(), "20");
+        assert_eq!(JsonValue::Number((-2.5).into()).to_string(), "-2.5");
+        assert_eq!(JsonValue::String("fulano".into()).to_string(), "\"fulano\"");
+    }
+
+    #[test]
+    fn test_display_escaped_string() {
+        let value = JsonValue::String("she said \"hi\"\n\\\u{0001}".into());
+        assert_eq!(value.to_string(), r#""she said \"hi\"\n\\\u0001""#);
+    }
+
+    #[test]
+    fn test_display_compact() {
+        let mut obj = JsonObject::new();
+        obj.insert("a".into(), JsonValue::Number(1.into()));
+
+        let value = JsonValue::Array(vec![
+            JsonValue::Object(obj),
+            JsonValue::Boolean(true),
+            JsonValue::Null,
+        ]);
+
+        assert_eq!(value.to_string(), r#"[{"a":1},true,null]"#);
+    }
+
+    #[test]
+    #[cfg(not(feature = "btreemap"))]
+    fn test_display_key_order() {
+        let input = r#"{"b":1,"a":2,"c":{"z":null,"y":[]}}"#;
+        assert_eq!(parse(input).to_string(), input);
+    }
+
+    #[test]
+    #[cfg(feature = "btreemap")]
+    fn test_display_sorted_keys() {
+        let json = parse(r#"{"b":1,"a":2,"c":{"z":null,"y":[]}}"#);
+
+        assert_eq!(json.to_string(), r#"{"a":2,"b":1,"c":{"y":[],"z":null}}"#);
+        assert_eq!(
+            json.to_pretty_string(2),
+            "{\n  \"a\": 2,\n  \"b\": 1,\n  \"c\": {\n    \"y\": [],\n    \"z\": null\n  }\n}"
+        );
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let value = parse(r#"[{"a": 1}, true, null]"#);
+        assert_eq!(parse(&value.to_string()), value);
+
+        let value = parse(r#"{"name": "fulano \"el\" tab\t", "nums": [1.5, -2e3, 0]}"#);
+        assert_eq!(parse(&value.to_string()), value);
+    }
+
+    #[test]
+    fn test_pretty_indent_2() {
+        let value = parse(r#"{"roles": ["admin", {"level": [1, 2]}]}"#);
+        let expected = r#"{
+  "roles": [
+    "admin",
+    {
+      "level": [
+        1,
+        2
+      ]
+    }
+  ]
+}"#;
+
+        assert_eq!(value.to_pretty_string(2), expected);
+    }
+
+    #[test]
+    fn test_pretty_indent_4() {
+        let value = parse(r#"{"roles": ["admin", {"level": [1, 2]}]}"#);
+        let expected = r#"{
+    "roles": [
+        "admin",
+        {
+            "level": [
+                1,
+                2
+            ]
+        }
+    ]
+}"#;
+
+        assert_eq!(value.to_pretty_string(4), expected);
+    }
+
+    #[test]
+    fn test_pretty_empty_containers() {
+        assert_eq!(parse("{}").to_pretty_string(2), "{}");
+        assert_eq!(parse("[]").to_pretty_string(2), "[]");
+        assert_eq!(parse("[{}, []]").to_pretty_string(2), "[\n  {},\n  []\n]");
+    }
+
+    #[test]
+    fn test_empty_containers_round_trip() {
+        let cases = [
+            ("{}", "{}"),
+            ("[]", "[]"),
+            ("[[]]", "[\n  []\n]"),
+            (r#"{"a":{}}"#, "{\n  \"a\": {}\n}"),
+            (r#"{"a":{},"b":[]}"#, "{\n  \"a\": {},\n  \"b\": []\n}"),
+        ];
+
+        for (compact, pretty) in cases {
+            let value = parse(compact);
+
+            assert_eq!(value.to_string(), compact);
+            assert_eq!(value.to_pretty_string(2), pretty);
+            assert_eq!(parse(pretty), value);
+        }
+    }
+
+    #[test]
+    fn test_colored_pretty() {
+        let value = parse(r#"{"a": ["x", 1, true, null, []]}"#);
+        let expected = "{\n  \x1b[1;34m\"a\"\x1b[0m: [\n    \x1b[32m\"x\"\x1b[0m,\n    \x1b[33m1\x1b[0m,\n    \x1b[35mtrue\x1b[0m,\n    \x1b[35mnull\x1b[0m,\n    []\n  ]\n}";
+
+        assert_eq!(value.to_colored_pretty_string(2), expected);
+    }
+
+    #[test]
+    fn test_to_writer_pretty() {
+        let value = parse(r#"{"roles": ["admin", {"level": [1, 2]}], "empty": {}, "ok": null}"#);
+
+        for indent in [0, 2, 4] {
+            let mut out = Vec::new();
+            value.to_writer_pretty(&mut out, indent).unwrap();
+            assert_eq!(
+                String::from_utf8(out).unwrap(),
+                value.to_pretty_string(indent)
+            );
+        }
+    }
+
+    #[test]
+    fn test_write_json_into_bytes() {
+        let value = parse(r#"{"a": [1, "b"]}"#);
+
+        let mut out = Vec::new();
+        write_json(&value, &mut out, &FormatOptions::default()).unwrap();
+        assert_eq!(out, br#"{"a":[1,"b"]}"#);
+
+        let mut out = Vec::new();
+        let options = FormatOptions {
+            indent: Some(2),
+            ..Default::default()
+        };
+        write_json(&value, &mut out, &options).unwrap();
+        assert_eq!(out, b"{\n  \"a\": [\n    1,\n    \"b\"\n  ]\n}");
+    }
+
+    fn write_to_string(value: &JsonValue, options: &FormatOptions) -> String {
+        let mut out = Vec::new();
+        write_json(value, &mut out, options).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_write_json_ascii_only() {
+        let value = parse(r#"{"café": ["😀", "a/b"]}"#);
+
+        // Raw UTF-8 unless asked otherwise
+        assert_eq!(
+            write_to_string(&value, &FormatOptions::default()),
+            r#"{"café":["😀","a/b"]}"#
+        );
+
+        let options = FormatOptions {
+            ascii_only: true,
+            ..Default::default()
+        };
+        let escaped = write_to_string(&value, &options);
+
+        assert_eq!(escaped, r#"{"caf\u00e9":["\ud83d\ude00","a/b"]}"#);
+        assert_eq!(parse(&escaped), value);
+
+        let options = FormatOptions {
+            indent: Some(2),
+            ascii_only: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            write_to_string(&value, &options),
+            "{\n  \"caf\\u00e9\": [\n    \"\\ud83d\\ude00\",\n    \"a/b\"\n  ]\n}"
+        );
+    }
+
+    #[test]
+    fn test_write_json_escape_slashes() {
+        let value = parse(r#"["</script>", {"a/b": "😀"}]"#);
+
+        let options = FormatOptions {
+            escape_slashes: true,
+            ..Default::default()
+        };
+        let escaped = write_to_string(&value, &options);
+
+        assert_eq!(escaped, r#"["<\/script>",{"a\/b":"😀"}]"#);
+        assert_eq!(parse(&escaped), value);
+    }
+
+    #[test]
+    fn test_write_json_io_error() {
+        let mut out = [0u8; 4];
+        let err = write_json(
+            &parse("[1, 2, 3]"),
+            &mut &mut out[..],
+            &FormatOptions::default(),
+        )
+        .unwrap_err();
+
+        assert_eq!(err.kind(), std::io::ErrorKind::WriteZero);
+    }
+
+    #[test]
+    fn test_canonical_reference_document() {
+        let input = r#"{
+            "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+            "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+            "literals": [null, true, false]
+        }"#;
+        let expected = r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#;
+
+        assert_eq!(parse(input).to_canonical_string(), expected);
+    }
+
+    #[test]
+    fn test_canonical_unicode_key_order() {
+        let input = r#"{
+            "\u20ac": "Euro Sign",
+            "\r": "Carriage Return",
+            "\ufb33": "Hebrew Letter Dalet With Dagesh",
+            "1": "One",
+            "\ud83d\ude00": "Emoji: Grinning Face",
+            "\u0080": "Control",
+            "\u00f6": "Latin Small Letter O With Diaeresis"
+        }"#;
+        let expected = "{\"\\r\":\"Carriage Return\",\"1\":\"One\",\"\u{80}\":\"Control\",\"\u{f6}\":\"Latin Small Letter O With Diaeresis\",\"\u{20ac}\":\"Euro Sign\",\"\u{1f600}\":\"Emoji: Grinning Face\",\"\u{fb33}\":\"Hebrew Letter Dalet With Dagesh\"}";
+
+        assert_eq!(parse(input).to_canonical_string(), expected);
+    }
+
+    #[test]
+    fn test_canonical_numbers() {
+        let cases = [
+            ("0", "0"),
+            ("-0", "0"),
+            ("-1.0", "-1"),
+            ("1e21", "1e+21"),
+            ("1e20", "100000000000000000000"),
+            ("9007199254740992", "9007199254740992"),
+            ("295147905179352830000", "295147905179352830000"),
+            ("1.7976931348623157e308", "1.7976931348623157e+308"),
+            ("5e-324", "5e-324"),
+            ("1e-7", "1e-7"),
+            ("0.000001", "0.000001"),
+            ("-123.456e-3", "-0.123456"),
+            ("1.5e300", "1.5e+300"),
+        ];
+
+        for (input, expected) in cases {
+            let value = parse(&format!("[{}]", input));
+            assert_eq!(value.to_canonical_string(), format!("[{}]", expected));
+        }
+    }
+
+    #[test]
+    fn test_canonical_ignores_key_order() {
+        let a = parse(r#"{"b": [1, {"d": 1, "c": 2.0}], "a": "x"}"#);
+        let b = parse(r#"{"a": "x", "b": [1.0, {"c": 2, "d": 1e0}]}"#);
+
+        assert_eq!(a.to_canonical_string(), b.to_canonical_string());
+    }
+}
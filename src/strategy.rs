@@ -0,0 +1,70 @@
+use crate::{
+    number::JsonNumber,
+    parser::{JsonObject, JsonValue},
+};
+use proptest::{collection, prelude::*};
+
+/// Numbers as the parser would read them back: integers in `Int` unless
+/// they only fit in a `u64`, and floats that are finite
+pub fn arb_json_number() -> impl Strategy<Value = JsonNumber> {
+    prop_oneof![
+        any::<i64>().prop_map(JsonNumber::from),
+        any::<u64>().prop_map(JsonNumber::from),
+        any::<f64>()
+            .prop_filter("NaN and infinities have no JSON representation", |n| {
+                n.is_finite()
+            })
+            .prop_map(JsonNumber::from),
+    ]
+}
+
+/// Arbitrary JSON values for property tests. Containers are built up from
+/// scalars, so failing cases shrink towards small and shallow values
+///
+/// ```
+/// use crusty_json::{arb_json_value, JsonValue};
+/// use proptest::{
+///     strategy::{Strategy, ValueTree},
+///     test_runner::TestRunner,
+/// };
+///
+/// let mut runner = TestRunner::default();
+/// let value = arb_json_value().new_tree(&mut runner).unwrap().current();
+///
+/// let text = JsonValue::Array(vec![value.clone()]).to_string();
+/// assert_eq!(text.parse::<JsonValue>().unwrap(), JsonValue::Array(vec![value]));
+/// ```
+pub fn arb_json_value() -> impl Strategy<Value = JsonValue> {
+    let leaf = prop_oneof![
+        Just(JsonValue::Null),
+        any::<bool>().prop_map(JsonValue::Boolean),
+        arb_json_number().prop_map(JsonValue::Number),
+        any::<String>().prop_map(JsonValue::String),
+    ];
+
+    leaf.prop_recursive(4, 64, 8, |inner| {
+        prop_oneof![
+            collection::vec(inner.clone(), 0..8).prop_map(JsonValue::Array),
+            collection::vec((any::<String>(), inner), 0..8)
+                .prop_map(|entries| JsonValue::Object(entries.into_iter().collect::<JsonObject>())),
+        ]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::arb_json_value;
+    use crate::parser::JsonValue;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn test_round_trip(value in arb_json_value()) {
+            // Only containers are allowed at the root
+            let doc = JsonValue::Array(vec![value]);
+
+            prop_assert_eq!(doc.to_string().parse::<JsonValue>().unwrap(), doc.clone());
+            prop_assert_eq!(doc.to_pretty_string(2).parse::<JsonValue>().unwrap(), doc);
+        }
+    }
+}
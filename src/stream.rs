@@ -0,0 +1,138 @@
+use crate::{
+    error::JsonError,
+    lexer::{Lexer, LexerOptions},
+    parser::{parser_next_root, JsonValue, ParserOptions},
+};
+use std::iter;
+
+/// Parses root values one after another from the same lexer, so spans keep
+/// pointing at the right line of the whole input
+struct Stream<'a> {
+    lexer: Lexer<'a>,
+    failed: bool,
+}
+
+impl<'a> Iterator for Stream<'a> {
+    type Item = Result<JsonValue, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        // Only whitespace left means the stream ended cleanly
+        let first = match self.lexer.next()? {
+            Ok(token) => token,
+            Err(err) => {
+                self.failed = true;
+                return Some(Err(err.into()));
+            }
+        };
+
+        let mut lex_error = None;
+        let tokens =
+            iter::once(Ok(first))
+                .chain(self.lexer.by_ref())
+                .map_while(|token| match token {
+                    Ok((token, span)) => Some((token.into_owned(), span)),
+                    Err(err) => {
+                        lex_error = Some(err);
+                        None
+                    }
+                });
+        let json = parser_next_root(tokens, &ParserOptions::default());
+
+        // There's no telling where the next value starts after a broken one
+        self.failed = lex_error.is_some() || json.is_err();
+
+        match lex_error {
+            Some(err) => Some(Err(err.into())),
+            None => Some(json.map_err(JsonError::from)),
+        }
+    }
+}
+
+/// Parses consecutive root values separated by nothing but whitespace, as
+/// found in newline-delimited JSON. Iteration stops after the first error
+///
+/// ```
+/// use crusty_json::parse_stream;
+///
+/// let values: Vec<_> = parse_stream("{\"a\":1}\n\n[2]\n").collect();
+/// assert_eq!(values.len(), 2);
+/// ```
+pub fn parse_stream(input: &str) -> impl Iterator<Item = Result<JsonValue, JsonError>> + '_ {
+    Stream {
+        lexer: Lexer::new(input, &LexerOptions::default()),
+        failed: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_stream;
+    use crate::{
+        error::JsonError,
+        lexer::{JsonTokenError, LexError, Span},
+        parser::{JsonParseError, JsonValue},
+    };
+
+    #[test]
+    fn test_three_objects_on_three_lines() {
+        let input = "{\"id\": 1}\n{\"id\": 2}\n{\"id\": 3}\n";
+        let values: Vec<JsonValue> = parse_stream(input).map(Result::unwrap).collect();
+
+        assert_eq!(values.len(), 3);
+        for (i, value) in values.iter().enumerate() {
+            assert_eq!(value["id"], JsonValue::Number((i as i64 + 1).into()));
+        }
+    }
+
+    #[test]
+    fn test_values_separated_by_whitespace() {
+        let values: Vec<JsonValue> = parse_stream("[1] \t{\"a\": true}")
+            .map(Result::unwrap)
+            .collect();
+
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[0][0], JsonValue::Number(1.into()));
+        assert_eq!(values[1]["a"], JsonValue::Boolean(true));
+    }
+
+    #[test]
+    fn test_blank_lines() {
+        let input = "\n\n{}\n   \n\n[]\n\n";
+        assert_eq!(parse_stream(input).count(), 2);
+        assert_eq!(parse_stream("").count(), 0);
+        assert_eq!(parse_stream(" \n\r\n ").count(), 0);
+    }
+
+    #[test]
+    fn test_stops_after_error() {
+        let mut values = parse_stream("{}\n{\"a\" 1}\n{}");
+
+        assert!(values.next().unwrap().is_ok());
+        assert!(matches!(
+            values.next(),
+            Some(Err(JsonError::Parse(
+                JsonParseError::ExpectedColonAfterKey(_, Span { line: 2, col: 6 })
+            )))
+        ));
+        assert!(values.next().is_none());
+    }
+
+    #[test]
+    fn test_lexer_error() {
+        let mut values = parse_stream("[1]\n[tru]");
+
+        assert!(values.next().unwrap().is_ok());
+        assert!(matches!(
+            values.next(),
+            Some(Err(JsonError::Token(LexError {
+                error: JsonTokenError::InvalidLiteral(_),
+                ..
+            })))
+        ));
+        assert!(values.next().is_none());
+    }
+}
@@ -1,20 +1,178 @@
-use crate::{
-    lexer::{lexer, JsonToken},
-    parser::{parser, JsonValue},
+use crusty_json::{
+    check_balanced, json_diff, lexer, parser, write_json, FormatOptions, JsonToken, JsonValue,
 };
+use std::io::{self, Write};
 
-fn parse_json(text: String) -> anyhow::Result<(Vec<JsonToken>, JsonValue)> {
-    let tokens = lexer(text)?;
-    let json = parser(&tokens)?;
-    return Ok((tokens, json));
+fn parse_json(text: &str) -> anyhow::Result<(Vec<JsonToken>, JsonValue)> {
+    let spanned_tokens = lexer(text)?;
+    // Unbalanced brackets get pointed at directly instead of failing wherever the parser gives up
+    check_balanced(&spanned_tokens)?;
+    let json = parser(&spanned_tokens)?;
+    let tokens = spanned_tokens.into_iter().map(|(token, _)| token).collect();
+    Ok((tokens, json))
 }
 
-pub fn parse_json_and_print(text: String) {
+pub struct PrintOptions {
+    /// Print indented JSON, which is the default unless a value is queried
+    pub pretty: bool,
+    /// Print JSON without any insignificant whitespace
+    pub compact: bool,
+    /// Print nothing on success, only errors
+    pub validate: bool,
+    /// JSON Pointer selecting the value to print instead of the whole document
+    pub query: Option<String>,
+    /// Highlight pretty-printed JSON with ANSI colors
+    pub color: bool,
+    /// Sort object keys at every level before printing
+    pub sort_keys: bool,
+    /// Treat every non-blank line as a separate JSON document
+    pub ndjson: bool,
+    /// Document to compare against, printing the differences instead of the input
+    pub diff: Option<JsonValue>,
+    /// Print metrics about the document instead of the document itself
+    pub stats: bool,
+    /// Dump the tokens the lexer produced before the rest of the output
+    pub tokens: bool,
+    /// Write every non-ASCII character as a `\uXXXX` escape
+    pub escape_non_ascii: bool,
+}
+
+/// Parses the text and prints it according to the options, returning whether it was valid JSON
+pub fn parse_json_and_print(text: &str, options: &PrintOptions, out: &mut impl Write) -> bool {
+    if options.ndjson {
+        return parse_ndjson_and_print(text, options, out);
+    }
+
     match parse_json(text) {
-        Ok((tokens, json)) => {
-            println!("Tokens: {:?}", tokens);
-            println!("JSON: {:?}", json);
+        Ok((tokens, json)) => print_json(tokens, json, options, out),
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            false
+        }
+    }
+}
+
+/// Parses and prints every line on its own, carrying on past the ones that fail
+fn parse_ndjson_and_print(text: &str, options: &PrintOptions, out: &mut impl Write) -> bool {
+    let mut success = true;
+
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
         }
-        Err(err) => eprintln!("Error: {}", err),
+
+        success &= match parse_json(line) {
+            Ok((tokens, json)) => print_json(tokens, json, options, out),
+            Err(err) => {
+                eprintln!("Error: line {}: {}", i + 1, err);
+                false
+            }
+        };
+    }
+
+    success
+}
+
+fn print_json(
+    tokens: Vec<JsonToken>,
+    mut json: JsonValue,
+    options: &PrintOptions,
+    out: &mut impl Write,
+) -> bool {
+    if options.tokens {
+        if let Err(err) = writeln!(out, "Tokens: {:?}", tokens) {
+            eprintln!("Error: {}", err);
+            return false;
+        }
+    }
+
+    if options.sort_keys {
+        json.sort_keys();
+    }
+
+    if let Some(other) = &options.diff {
+        return print_diff(&json, other, out);
+    }
+
+    let json = match &options.query {
+        Some(pointer) => match json.pointer(pointer) {
+            Some(value) => value,
+            None => {
+                eprintln!("Error: No value found at `{}`", pointer);
+                return false;
+            }
+        },
+        None => &json,
+    };
+
+    let written = if options.validate {
+        // Nothing to print, the exit code tells whether it's valid
+        Ok(())
+    } else if options.stats {
+        print_stats(json, out)
+    } else {
+        let format = FormatOptions {
+            // A queried value is usually small, so it stays on one line unless asked
+            indent: (options.pretty || !(options.compact || options.query.is_some())).then_some(2),
+            color: options.color,
+            ascii_only: options.escape_non_ascii,
+            ..Default::default()
+        };
+        write_json(json, out, &format).and_then(|_| writeln!(out))
     };
+
+    match written {
+        Ok(()) => true,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            false
+        }
+    }
+}
+
+/// Prints how many values of each type there are, along with the overall shape
+fn print_stats(json: &JsonValue, out: &mut impl Write) -> io::Result<()> {
+    let mut counts = [0; 6];
+    let mut longest_string = 0;
+
+    for (_, value) in json.walk() {
+        let i = match value {
+            JsonValue::Object(_) => 0,
+            JsonValue::Array(_) => 1,
+            JsonValue::String(json_string) => {
+                longest_string = longest_string.max(json_string.chars().count());
+                2
+            }
+            JsonValue::Number(_) => 3,
+            JsonValue::Boolean(_) => 4,
+            JsonValue::Null => 5,
+        };
+        counts[i] += 1;
+    }
+
+    writeln!(out, "Nodes: {}", json.node_count())?;
+    writeln!(out, "Depth: {}", json.depth())?;
+    for (name, count) in [
+        "Objects", "Arrays", "Strings", "Numbers", "Booleans", "Nulls",
+    ]
+    .iter()
+    .zip(counts)
+    {
+        writeln!(out, "{}: {}", name, count)?;
+    }
+    writeln!(out, "Longest string: {}", longest_string)
+}
+
+/// Prints one line per difference, returning whether the documents were equal
+fn print_diff(json: &JsonValue, other: &JsonValue, out: &mut impl Write) -> bool {
+    let differences = json_diff(json, other);
+
+    for difference in &differences {
+        if let Err(err) = writeln!(out, "{}", difference) {
+            eprintln!("Error: {}", err);
+            return false;
+        }
+    }
+
+    differences.is_empty()
 }
@@ -0,0 +1,833 @@
+use crate::{
+    lexer::validate_number,
+    number::JsonNumber,
+    parser::{JsonObject, JsonValue},
+};
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    ops::Index,
+};
+use thiserror::Error;
+
+/// A value turned out to be of a different type than the one asked for
+#[derive(Error, Debug, PartialEq)]
+#[error("Expected {expected}, found {found}")]
+pub struct JsonTypeError {
+    pub expected: &'static str,
+    pub found: &'static str,
+}
+
+static NULL: JsonValue = JsonValue::Null;
+
+impl JsonValue {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(json_string) => Some(json_string),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::Number(json_number) => Some(json_number.as_f64()),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            JsonValue::Number(json_number) => json_number.as_i64(),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            JsonValue::Number(json_number) => json_number.as_u64(),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Boolean(json_boolean) => Some(*json_boolean),
+            _ => None,
+        }
+    }
+
+    /// Like [`JsonValue::as_bool`], but also accepts the strings `"true"` and
+    /// `"false"` in any case, as found in stringly typed config
+    pub fn coerce_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Boolean(json_boolean) => Some(*json_boolean),
+            JsonValue::String(json_string) => match json_string.trim() {
+                s if s.eq_ignore_ascii_case("true") => Some(true),
+                s if s.eq_ignore_ascii_case("false") => Some(false),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Like [`JsonValue::as_f64`], but also accepts strings holding a valid
+    /// JSON number like `"42.5"`, as found in stringly typed config
+    pub fn coerce_number(&self) -> Option<f64> {
+        match self {
+            JsonValue::Number(json_number) => Some(json_number.as_f64()),
+            JsonValue::String(json_string) => {
+                let text = json_string.trim();
+                validate_number(text).ok()?;
+                JsonNumber::from_token(text).map(|number| number.as_f64())
+            }
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<JsonValue>> {
+        match self {
+            JsonValue::Array(arr) => Some(arr),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&JsonObject> {
+        match self {
+            JsonValue::Object(obj) => Some(obj),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, JsonValue::Null)
+    }
+
+    /// Name of the JSON type of the value, like `"string"` or `"object"`, as
+    /// used in error messages
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            JsonValue::String(_) => "string",
+            JsonValue::Number(_) => "number",
+            JsonValue::Boolean(_) => "boolean",
+            JsonValue::Null => "null",
+            JsonValue::Array(_) => "array",
+            JsonValue::Object(_) => "object",
+        }
+    }
+
+    fn type_error(&self, expected: &'static str) -> JsonTypeError {
+        JsonTypeError {
+            expected,
+            found: self.type_name(),
+        }
+    }
+
+    /// Same as [`JsonValue::as_str`], failing with the type found instead
+    pub fn expect_str(&self) -> Result<&str, JsonTypeError> {
+        self.as_str().ok_or_else(|| self.type_error("string"))
+    }
+
+    /// Same as [`JsonValue::as_f64`], failing with the type found instead
+    pub fn expect_f64(&self) -> Result<f64, JsonTypeError> {
+        self.as_f64().ok_or_else(|| self.type_error("number"))
+    }
+
+    /// Same as [`JsonValue::as_bool`], failing with the type found instead
+    pub fn expect_bool(&self) -> Result<bool, JsonTypeError> {
+        self.as_bool().ok_or_else(|| self.type_error("boolean"))
+    }
+
+    /// Same as [`JsonValue::as_array`], failing with the type found instead
+    pub fn expect_array(&self) -> Result<&Vec<JsonValue>, JsonTypeError> {
+        self.as_array().ok_or_else(|| self.type_error("array"))
+    }
+
+    /// Same as [`JsonValue::as_object`], failing with the type found instead
+    pub fn expect_object(&self) -> Result<&JsonObject, JsonTypeError> {
+        self.as_object().ok_or_else(|| self.type_error("object"))
+    }
+
+    /// Looks up an object member, returning `None` for missing keys and non-object values
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        self.as_object()?.get(key)
+    }
+
+    /// Iterates over the keys of an object in source order, or `None` for non-object values
+    pub fn object_keys(&self) -> Option<impl Iterator<Item = &String>> {
+        Some(self.as_object()?.keys())
+    }
+
+    /// Iterates over the elements of an array, or `None` for non-array values
+    pub fn iter_array(&self) -> Option<impl Iterator<Item = &JsonValue>> {
+        Some(self.as_array()?.iter())
+    }
+
+    /// Iterates over the members of an object in source order, or `None` for non-object values
+    pub fn iter_object(&self) -> Option<impl Iterator<Item = (&String, &JsonValue)>> {
+        Some(self.as_object()?.iter())
+    }
+
+    /// Turns an array into an iterator over its elements, or `None` for non-array values
+    pub fn into_array(mut self) -> Option<impl Iterator<Item = JsonValue>> {
+        match &mut self {
+            JsonValue::Array(arr) => Some(std::mem::take(arr).into_iter()),
+            _ => None,
+        }
+    }
+
+    /// Turns an object into an iterator over its members in source order, or
+    /// `None` for non-object values
+    pub fn into_object(mut self) -> Option<impl Iterator<Item = (String, JsonValue)>> {
+        match &mut self {
+            JsonValue::Object(obj) => Some(std::mem::take(obj).into_iter()),
+            _ => None,
+        }
+    }
+
+    /// Whether this is an object with a member named `key`
+    pub fn has_key(&self, key: &str) -> bool {
+        self.as_object().is_some_and(|obj| obj.contains_key(key))
+    }
+
+    /// Compares like `==`, except numbers only need to be within `epsilon` of
+    /// each other, which suits values that went through floating point math
+    pub fn approx_eq(&self, other: &JsonValue, epsilon: f64) -> bool {
+        match (self, other) {
+            (JsonValue::Number(a), JsonValue::Number(b)) => {
+                a == b || (a.as_f64() - b.as_f64()).abs() <= epsilon
+            }
+            (JsonValue::Array(a), JsonValue::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.approx_eq(b, epsilon))
+            }
+            (JsonValue::Object(a), JsonValue::Object(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .all(|(key, a)| b.get(key).is_some_and(|b| a.approx_eq(b, epsilon)))
+            }
+            _ => self == other,
+        }
+    }
+
+    /// Compares like `==`, except numbers are equal whenever their values
+    /// are, so `1` equals `1.0` and `NaN` equals `NaN`
+    pub fn value_eq(&self, other: &JsonValue) -> bool {
+        match (self, other) {
+            (JsonValue::Number(a), JsonValue::Number(b)) => a.value_eq(b),
+            (JsonValue::Array(a), JsonValue::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.value_eq(b))
+            }
+            (JsonValue::Object(a), JsonValue::Object(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .all(|(key, a)| b.get(key).is_some_and(|b| a.value_eq(b)))
+            }
+            _ => self == other,
+        }
+    }
+
+    /// Maximum nesting level, where scalars and empty containers count as 1
+    pub fn depth(&self) -> usize {
+        let children = match self {
+            JsonValue::Array(arr) => arr.iter().map(JsonValue::depth).max(),
+            JsonValue::Object(obj) => obj.values().map(JsonValue::depth).max(),
+            _ => None,
+        };
+
+        1 + children.unwrap_or(0)
+    }
+
+    /// Deep-merges `other` into this value. Objects are merged key by key; in
+    /// every other case, type conflicts included, the value from `other` wins
+    pub fn merge(&mut self, mut other: JsonValue) {
+        match (self, &mut other) {
+            (JsonValue::Object(obj), JsonValue::Object(other_obj)) => {
+                for (key, value) in std::mem::take(other_obj) {
+                    match obj.get_mut(&key) {
+                        Some(existing) => existing.merge(value),
+                        None => {
+                            obj.insert(key, value);
+                        }
+                    }
+                }
+            }
+            (this, _) => *this = other,
+        }
+    }
+
+    /// Sorts object keys lexicographically at every nesting level, so the
+    /// serialized output no longer depends on the source key order
+    pub fn sort_keys(&mut self) {
+        match self {
+            JsonValue::Array(arr) => arr.iter_mut().for_each(JsonValue::sort_keys),
+            JsonValue::Object(obj) => {
+                // A `BTreeMap` is sorted already
+                #[cfg(not(feature = "btreemap"))]
+                obj.sort_keys();
+                obj.values_mut().for_each(JsonValue::sort_keys);
+            }
+            _ => {}
+        }
+    }
+
+    /// Number of values in the tree, this one included. Object keys don't count
+    pub fn node_count(&self) -> usize {
+        let children: usize = match self {
+            JsonValue::Array(arr) => arr.iter().map(JsonValue::node_count).sum(),
+            JsonValue::Object(obj) => obj.values().map(JsonValue::node_count).sum(),
+            _ => 0,
+        };
+
+        1 + children
+    }
+}
+
+/// Takes nested values apart one level at a time on a heap stack, so dropping
+/// a deeply nested value can't overflow the call stack
+impl Drop for JsonValue {
+    fn drop(&mut self) {
+        let mut stack = match self {
+            JsonValue::Array(arr) if !arr.is_empty() => std::mem::take(arr),
+            JsonValue::Object(obj) if !obj.is_empty() => {
+                std::mem::take(obj).into_values().collect()
+            }
+            _ => return,
+        };
+
+        // Every value popped has its children moved out first, so dropping it
+        // returns right away
+        while let Some(mut value) = stack.pop() {
+            match &mut value {
+                JsonValue::Array(arr) => stack.append(arr),
+                JsonValue::Object(obj) => stack.extend(std::mem::take(obj).into_values()),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Hashes consistently with `==`, so object member order doesn't matter
+impl Hash for JsonValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+
+        match self {
+            JsonValue::String(json_string) => json_string.hash(state),
+            JsonValue::Number(json_number) => json_number.hash(state),
+            JsonValue::Boolean(json_boolean) => json_boolean.hash(state),
+            JsonValue::Null => {}
+            JsonValue::Array(arr) => arr.hash(state),
+            JsonValue::Object(obj) => {
+                // Members are hashed on their own and summed up, which any order adds up to
+                let members = obj.iter().fold(0u64, |sum, member| {
+                    let mut hasher = DefaultHasher::new();
+                    member.hash(&mut hasher);
+                    sum.wrapping_add(hasher.finish())
+                });
+
+                obj.len().hash(state);
+                members.hash(state);
+            }
+        }
+    }
+}
+
+/// Lets values key hash sets and maps. A `NaN` number is never equal to
+/// itself, which only non-finite numbers parsed on purpose or built by hand
+/// can hold. Raw numbers compare by their text, so `1e2` is not `100`
+impl Eq for JsonValue {}
+
+/// Looks up an object member. Missing keys and non-object values yield
+/// `JsonValue::Null` instead of panicking, so lookups can be chained freely
+impl Index<&str> for JsonValue {
+    type Output = JsonValue;
+
+    fn index(&self, key: &str) -> &JsonValue {
+        self.get(key).unwrap_or(&NULL)
+    }
+}
+
+/// Looks up an array element. Out-of-bounds indices and non-array values
+/// yield `JsonValue::Null` instead of panicking, so lookups can be chained freely
+impl Index<usize> for JsonValue {
+    type Output = JsonValue;
+
+    fn index(&self, index: usize) -> &JsonValue {
+        match self {
+            JsonValue::Array(arr) => arr.get(index).unwrap_or(&NULL),
+            _ => &NULL,
+        }
+    }
+}
+
+// Comparisons against native values are false whenever the variant doesn't match
+
+impl PartialEq<str> for JsonValue {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == Some(other)
+    }
+}
+
+impl PartialEq<&str> for JsonValue {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == Some(*other)
+    }
+}
+
+impl PartialEq<String> for JsonValue {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == Some(other.as_str())
+    }
+}
+
+impl PartialEq<f64> for JsonValue {
+    fn eq(&self, other: &f64) -> bool {
+        self.as_f64() == Some(*other)
+    }
+}
+
+impl PartialEq<i64> for JsonValue {
+    fn eq(&self, other: &i64) -> bool {
+        self.as_i64() == Some(*other)
+    }
+}
+
+impl PartialEq<bool> for JsonValue {
+    fn eq(&self, other: &bool) -> bool {
+        self.as_bool() == Some(*other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::from_str;
+    use std::{
+        collections::{hash_map::DefaultHasher, HashSet},
+        hash::{Hash, Hasher},
+    };
+
+    use super::{JsonTypeError, JsonValue};
+
+    fn hash_of(value: &JsonValue) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_as_str() {
+        assert_eq!(JsonValue::String("fulano".into()).as_str(), Some("fulano"));
+        assert_eq!(JsonValue::Number(1.into()).as_str(), None);
+    }
+
+    #[test]
+    fn test_as_f64() {
+        assert_eq!(JsonValue::Number(2.5.into()).as_f64(), Some(2.5));
+        assert_eq!(JsonValue::String("2.5".into()).as_f64(), None);
+    }
+
+    #[test]
+    fn test_as_bool() {
+        assert_eq!(JsonValue::Boolean(true).as_bool(), Some(true));
+        assert_eq!(JsonValue::Null.as_bool(), None);
+    }
+
+    #[test]
+    fn test_coerce_bool() {
+        assert_eq!(json!("true").coerce_bool(), Some(true));
+        assert_eq!(json!(" FALSE ").coerce_bool(), Some(false));
+        assert_eq!(json!(true).coerce_bool(), Some(true));
+        assert_eq!(json!("banana").coerce_bool(), None);
+        assert_eq!(json!("1").coerce_bool(), None);
+        assert_eq!(json!(1).coerce_bool(), None);
+
+        assert_eq!(json!("true").as_bool(), None);
+    }
+
+    #[test]
+    fn test_coerce_number() {
+        assert_eq!(json!("42.5").coerce_number(), Some(42.5));
+        assert_eq!(json!(" -1e3\n").coerce_number(), Some(-1000.0));
+        assert_eq!(json!(7).coerce_number(), Some(7.0));
+        assert_eq!(json!("banana").coerce_number(), None);
+        assert_eq!(json!("NaN").coerce_number(), None);
+        assert_eq!(json!("+1").coerce_number(), None);
+        assert_eq!(json!("").coerce_number(), None);
+        assert_eq!(json!(true).coerce_number(), None);
+
+        assert_eq!(json!("42.5").as_f64(), None);
+    }
+
+    #[test]
+    fn test_as_array() {
+        let json = from_str("[1, 2]").unwrap();
+
+        assert_eq!(
+            json.as_array(),
+            Some(&vec![
+                JsonValue::Number(1.into()),
+                JsonValue::Number(2.into())
+            ])
+        );
+        assert_eq!(JsonValue::Null.as_array(), None);
+    }
+
+    #[test]
+    fn test_as_object() {
+        let json = from_str(r#"{"a": 1}"#).unwrap();
+
+        let obj = json.as_object().unwrap();
+        assert_eq!(obj.len(), 1);
+        assert_eq!(obj["a"], JsonValue::Number(1.into()));
+
+        assert_eq!(from_str("[]").unwrap().as_object(), None);
+    }
+
+    #[test]
+    fn test_is_null() {
+        assert!(JsonValue::Null.is_null());
+        assert!(!JsonValue::Boolean(false).is_null());
+    }
+
+    #[test]
+    fn test_get() {
+        let json = from_str(r#"{"a": 1}"#).unwrap();
+
+        assert_eq!(json.get("a"), Some(&JsonValue::Number(1.into())));
+        assert_eq!(json.get("b"), None);
+        assert_eq!(from_str(r#"["a"]"#).unwrap().get("a"), None);
+    }
+
+    #[test]
+    #[cfg(not(feature = "btreemap"))]
+    fn test_object_keys() {
+        let json = from_str(r#"{"b": 1, "a": {"c": null}}"#).unwrap();
+
+        let keys: Vec<&String> = json.object_keys().unwrap().collect();
+        assert_eq!(keys, ["b", "a"]);
+        assert!(json.has_key("a"));
+        assert!(!json.has_key("c"));
+    }
+
+    #[test]
+    fn test_object_keys_non_object() {
+        let json = from_str(r#"["a", "b"]"#).unwrap();
+
+        assert!(json.object_keys().is_none());
+        assert!(!json.has_key("a"));
+        assert!(JsonValue::String("a".into()).object_keys().is_none());
+    }
+
+    #[test]
+    fn test_iter_array() {
+        let json = from_str(r#"[1, "a", null]"#).unwrap();
+
+        let borrowed: Vec<&JsonValue> = json.iter_array().unwrap().collect();
+        assert_eq!(borrowed, [&json!(1), &json!("a"), &JsonValue::Null]);
+
+        let owned: Vec<JsonValue> = json.into_array().unwrap().collect();
+        assert_eq!(owned, [json!(1), json!("a"), JsonValue::Null]);
+
+        assert!(json!({"a": 1}).iter_array().is_none());
+        assert!(json!({"a": 1}).into_array().is_none());
+    }
+
+    #[test]
+    #[cfg(not(feature = "btreemap"))]
+    fn test_iter_object() {
+        let json = from_str(r#"{"b": 1, "a": [true]}"#).unwrap();
+
+        let borrowed: Vec<(&String, &JsonValue)> = json.iter_object().unwrap().collect();
+        assert_eq!(
+            borrowed,
+            [
+                (&"b".to_string(), &json!(1)),
+                (&"a".to_string(), &json!([true]))
+            ]
+        );
+
+        let owned: Vec<(String, JsonValue)> = json.into_object().unwrap().collect();
+        assert_eq!(
+            owned,
+            [
+                ("b".to_string(), json!(1)),
+                ("a".to_string(), json!([true]))
+            ]
+        );
+
+        assert!(json!([1]).iter_object().is_none());
+        assert!(JsonValue::Null.into_object().is_none());
+    }
+
+    #[test]
+    fn test_index_access() {
+        let json = from_str(r#"{"user": {"name": "fulano", "roles": ["admin", "dev"]}}"#).unwrap();
+
+        assert_eq!(json["user"]["name"], JsonValue::String("fulano".into()));
+        assert_eq!(json["user"]["roles"][0], JsonValue::String("admin".into()));
+        assert_eq!(json["user"]["roles"][1], JsonValue::String("dev".into()));
+    }
+
+    #[test]
+    fn test_index_missing_key() {
+        let json = from_str(r#"{"user": {"name": "fulano"}}"#).unwrap();
+
+        assert_eq!(json["admin"], JsonValue::Null);
+        assert_eq!(json["user"]["age"], JsonValue::Null);
+        assert_eq!(json["user"]["name"]["first"], JsonValue::Null);
+    }
+
+    #[test]
+    fn test_index_out_of_bounds() {
+        let json = from_str(r#"[1, [2]]"#).unwrap();
+
+        assert_eq!(json[2], JsonValue::Null);
+        assert_eq!(json[1][1], JsonValue::Null);
+        assert_eq!(json[0][0], JsonValue::Null);
+    }
+
+    #[test]
+    fn test_eq_str() {
+        let value = JsonValue::String("hello".into());
+        let greeting = String::from("hello");
+
+        assert!(value == "hello");
+        assert!(value == *"hello");
+        assert!(value == greeting);
+        assert!(value != "bye");
+        assert!(JsonValue::Null != "null");
+    }
+
+    #[test]
+    fn test_eq_f64() {
+        let value = JsonValue::Number(42.into());
+
+        assert!(value == 42.0);
+        assert!(value != 42.5);
+        assert!(JsonValue::String("42".into()) != 42.0);
+    }
+
+    #[test]
+    fn test_eq_bool() {
+        assert!(JsonValue::Boolean(true) == true);
+        assert!(JsonValue::Boolean(false) != true);
+        assert!(JsonValue::Null != false);
+    }
+
+    #[test]
+    fn test_depth_and_node_count_scalar() {
+        let value = JsonValue::Boolean(true);
+
+        assert_eq!(value.depth(), 1);
+        assert_eq!(value.node_count(), 1);
+    }
+
+    #[test]
+    fn test_depth_and_node_count_flat_array() {
+        assert_eq!(from_str("[]").unwrap().depth(), 1);
+        assert_eq!(from_str("[]").unwrap().node_count(), 1);
+
+        let json = from_str("[1, 2, 3]").unwrap();
+
+        assert_eq!(json.depth(), 2);
+        assert_eq!(json.node_count(), 4);
+    }
+
+    #[test]
+    fn test_depth_and_node_count_nested_object() {
+        let json = from_str(r#"{"a": {"b": [1, {"c": null}]}, "d": {}}"#).unwrap();
+
+        assert_eq!(json.depth(), 5);
+        assert_eq!(json.node_count(), 7);
+    }
+
+    #[test]
+    fn test_merge_nested_objects() {
+        let mut json =
+            from_str(r#"{"db": {"host": "localhost", "port": 5432}, "debug": false}"#).unwrap();
+        let overrides =
+            from_str(r#"{"db": {"port": 6543, "user": "admin"}, "name": "app"}"#).unwrap();
+
+        json.merge(overrides);
+
+        assert_eq!(
+            json,
+            from_str(
+                r#"{"db": {"host": "localhost", "port": 6543, "user": "admin"}, "debug": false, "name": "app"}"#
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_merge_overwrites_arrays() {
+        let mut json = from_str(r#"{"roles": ["admin", "user"]}"#).unwrap();
+
+        json.merge(from_str(r#"{"roles": ["guest"]}"#).unwrap());
+
+        assert_eq!(json, from_str(r#"{"roles": ["guest"]}"#).unwrap());
+    }
+
+    #[test]
+    fn test_merge_type_conflicts() {
+        let mut json = from_str(r#"{"a": {"b": 1}, "c": 2}"#).unwrap();
+
+        json.merge(from_str(r#"{"a": null, "c": {"d": 3}}"#).unwrap());
+
+        assert_eq!(json, from_str(r#"{"a": null, "c": {"d": 3}}"#).unwrap());
+
+        let mut json = from_str(r#"{"a": 1}"#).unwrap();
+        json.merge(from_str("[1]").unwrap());
+
+        assert_eq!(json, from_str("[1]").unwrap());
+    }
+
+    #[test]
+    fn test_sort_keys() {
+        let mut json = from_str(r#"{"b":1,"a":{"d":1,"c":2},"c":[{"z":0,"y":0}]}"#).unwrap();
+
+        json.sort_keys();
+
+        assert_eq!(
+            json.to_string(),
+            r#"{"a":{"c":2,"d":1},"b":1,"c":[{"y":0,"z":0}]}"#
+        );
+    }
+
+    #[test]
+    fn test_hash_ignores_key_order() {
+        let a = from_str(r#"{"a": 1, "b": {"c": [true, null], "d": "x"}}"#).unwrap();
+        let b = from_str(r#"{"b": {"d": "x", "c": [true, null]}, "a": 1}"#).unwrap();
+
+        assert_eq!(a, b);
+        assert_eq!(hash_of(&a), hash_of(&b));
+    }
+
+    #[test]
+    fn test_hash_set_dedupes_reordered_objects() {
+        let mut set = HashSet::new();
+
+        assert!(set.insert(from_str(r#"{"a": 1, "b": [true]}"#).unwrap()));
+        assert!(!set.insert(from_str(r#"{"b": [true], "a": 1}"#).unwrap()));
+        assert!(set.insert(from_str(r#"{"a": 2, "b": [true]}"#).unwrap()));
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn test_hash_array_order() {
+        let a = from_str("[1, 2, 3]").unwrap();
+        let b = from_str("[3, 2, 1]").unwrap();
+        let c = from_str("[1, 2]").unwrap();
+
+        assert_ne!(hash_of(&a), hash_of(&b));
+        assert_ne!(hash_of(&a), hash_of(&c));
+    }
+
+    #[test]
+    fn test_hash_numbers() {
+        assert_eq!(hash_of(&json!(0.0)), hash_of(&json!(-0.0)));
+        assert_eq!(hash_of(&json!(f64::NAN)), hash_of(&json!(-f64::NAN)));
+        assert_ne!(hash_of(&json!(1)), hash_of(&json!(2)));
+        assert_ne!(hash_of(&json!({"a": 1})), hash_of(&json!({"a": "1"})));
+        assert_ne!(hash_of(&json!({"a": 1})), hash_of(&json!({"b": 1})));
+    }
+
+    #[test]
+    fn test_approx_eq() {
+        let computed = json!({"total": 1.0000001, "parts": [0.1, 0.2]});
+        let expected = json!({"parts": [0.1, 0.2], "total": 1.0});
+
+        assert_ne!(computed, expected);
+        assert!(!computed.approx_eq(&expected, 0.0));
+        assert!(computed.approx_eq(&expected, 1e-6));
+        assert!(!computed.approx_eq(&expected, 1e-8));
+    }
+
+    #[test]
+    fn test_approx_eq_structure() {
+        assert!(json!([1, 2]).approx_eq(&json!([1.0, 2.0]), 0.0));
+        assert!(!json!([1, 2]).approx_eq(&json!([1, 2, 3]), 1.0));
+        assert!(!json!({"a": 1}).approx_eq(&json!({"b": 1}), 1.0));
+        assert!(!json!("1").approx_eq(&json!(1), 1.0));
+        assert!(!json!(f64::NAN).approx_eq(&json!(f64::NAN), 1.0));
+    }
+
+    #[test]
+    fn test_value_eq() {
+        let a = json!({"n": [1, {"m": 2.5}], "s": "1"});
+        let b = json!({"s": "1", "n": [1.0, {"m": 2.5}]});
+
+        assert_ne!(a, b);
+        assert!(a.value_eq(&b));
+        assert!(json!([f64::NAN]).value_eq(&json!([f64::NAN])));
+        assert!(!json!("1").value_eq(&json!(1)));
+        assert!(!json!([1, 2]).value_eq(&json!([1])));
+        assert!(!json!({"a": 1}).value_eq(&json!({"b": 1})));
+    }
+
+    #[test]
+    fn test_clone() {
+        let value = json!({"a": [1, {"b": null}]});
+        let mut copy = value.clone();
+        assert_eq!(copy, value);
+
+        // Nested values are copied too, not shared with the original
+        copy.set_pointer("/a/1/b", true.into()).unwrap();
+        copy.set_pointer("/a/-", 2.into()).unwrap();
+
+        assert_eq!(value, json!({"a": [1, {"b": null}]}));
+        assert_eq!(copy, json!({"a": [1, {"b": true}, 2]}));
+    }
+
+    #[test]
+    fn test_type_name() {
+        let cases = [
+            (json!("a"), "string"),
+            (json!(1.5), "number"),
+            (json!(false), "boolean"),
+            (json!(null), "null"),
+            (json!([1]), "array"),
+            (json!({"a": 1}), "object"),
+        ];
+
+        for (value, name) in cases {
+            assert_eq!(value.type_name(), name);
+        }
+    }
+
+    #[test]
+    fn test_expect_types() -> Result<(), JsonTypeError> {
+        let json = json!({"name": "fulano", "age": 20, "admin": false, "roles": ["dev"]});
+
+        assert_eq!(json["name"].expect_str()?, "fulano");
+        assert_eq!(json["age"].expect_f64()?, 20.0);
+        assert!(!json["admin"].expect_bool()?);
+        assert_eq!(json["roles"].expect_array()?.len(), 1);
+        assert_eq!(json.expect_object()?.len(), 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_expect_type_mismatch() {
+        let json = json!({"age": "20", "roles": null});
+
+        let err = json["age"].expect_f64().unwrap_err();
+        assert_eq!(
+            err,
+            JsonTypeError {
+                expected: "number",
+                found: "string"
+            }
+        );
+        assert_eq!(err.to_string(), "Expected number, found string");
+
+        assert_eq!(
+            json["roles"].expect_array().unwrap_err().to_string(),
+            "Expected array, found null"
+        );
+        assert_eq!(
+            json.expect_str().unwrap_err().to_string(),
+            "Expected string, found object"
+        );
+    }
+}
@@ -0,0 +1,455 @@
+use crate::{
+    error::JsonError,
+    lexer::{JsonTokenRef, LexError, Lexer, LexerOptions, Span},
+    number::JsonNumber,
+    parser::{BracketContext, JsonParseError},
+};
+
+/// Receives parse events in document order. Every method does nothing by
+/// default, so implementors only override the events they care about
+pub trait Visitor {
+    fn begin_object(&mut self) {}
+    fn key(&mut self, _key: &str) {}
+    fn end_object(&mut self) {}
+    fn begin_array(&mut self) {}
+    fn end_array(&mut self) {}
+    fn string(&mut self, _value: &str) {}
+    fn number(&mut self, _value: JsonNumber) {}
+    fn bool(&mut self, _value: bool) {}
+    fn null(&mut self) {}
+}
+
+/// Borrowed token stream that remembers the span of the last token, like the
+/// parser's own token iterator
+struct Events<'a> {
+    lexer: Lexer<'a>,
+    span: Span,
+}
+
+impl<'a> Events<'a> {
+    fn next(&mut self) -> Result<Option<JsonTokenRef<'a>>, LexError> {
+        match self.lexer.next() {
+            Some(Ok((token, span))) => {
+                self.span = span;
+                Ok(Some(token))
+            }
+            Some(Err(err)) => Err(err),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Reads the start of a value and reports it, giving back the container it
+/// opens, if any, so its members get visited next
+fn visit_value<V: Visitor>(
+    token: Option<JsonTokenRef>,
+    context: BracketContext,
+    events: &mut Events,
+    visitor: &mut V,
+) -> Result<Option<BracketContext>, JsonError> {
+    match token {
+        Some(JsonTokenRef::String(json_string)) => visitor.string(&json_string),
+        Some(JsonTokenRef::Number(json_number)) => match JsonNumber::from_token(json_number) {
+            Some(number) => visitor.number(number),
+            None => {
+                return Err(JsonParseError::InvalidNumberValue(
+                    json_number.to_string(),
+                    events.span,
+                )
+                .into());
+            }
+        },
+        Some(JsonTokenRef::True) => visitor.bool(true),
+        Some(JsonTokenRef::False) => visitor.bool(false),
+        Some(JsonTokenRef::Null) => visitor.null(),
+        Some(JsonTokenRef::OpenCurlyBracket) => {
+            visitor.begin_object();
+            return Ok(Some(BracketContext::Object));
+        }
+        Some(JsonTokenRef::OpenSquareBracket) => {
+            visitor.begin_array();
+            return Ok(Some(BracketContext::Array));
+        }
+        Some(t) => {
+            let t = t.into_owned();
+            context.check(&t, events.span)?;
+            return Err(JsonParseError::InvalidValue(Some(t), events.span).into());
+        }
+        None => return Err(JsonParseError::InvalidValue(None, events.span).into()),
+    }
+
+    Ok(None)
+}
+
+/// Reads what follows `{` or a comma inside an object up to the colon,
+/// reporting the key of the next member, or gives back `false` if the object
+/// ends there
+fn visit_key<V: Visitor>(
+    events: &mut Events,
+    visitor: &mut V,
+    comma: Option<Span>,
+) -> Result<bool, JsonError> {
+    let token = events
+        .next()?
+        .ok_or(JsonParseError::ExpectedEndOfObject(events.span))?;
+
+    match token {
+        JsonTokenRef::CloseCurlyBracket => {
+            if let Some(comma_span) = comma {
+                return Err(JsonParseError::TrailingComma(comma_span).into());
+            }
+            visitor.end_object();
+            return Ok(false);
+        }
+        JsonTokenRef::String(key) => visitor.key(&key),
+        JsonTokenRef::Identifier(key) => visitor.key(key),
+        _ => {
+            let token = token.into_owned();
+            BracketContext::Object.check(&token, events.span)?;
+            return Err(JsonParseError::ExpectedObjectKey(token, events.span).into());
+        }
+    }
+
+    match events.next()? {
+        Some(JsonTokenRef::Colon) => Ok(true),
+        t => {
+            let t = t.map(JsonTokenRef::into_owned);
+            if let Some(t) = &t {
+                BracketContext::Object.check(t, events.span)?;
+            }
+            Err(JsonParseError::ExpectedColonAfterKey(t, events.span).into())
+        }
+    }
+}
+
+/// Reads what follows `[` or a comma inside an array, giving back the first
+/// token of the next element, or `None` if the array ends there
+fn visit_element<'a, V: Visitor>(
+    events: &mut Events<'a>,
+    visitor: &mut V,
+    comma: Option<Span>,
+) -> Result<Option<JsonTokenRef<'a>>, JsonError> {
+    match events.next()? {
+        Some(JsonTokenRef::CloseSquareBracket) => {
+            if let Some(comma_span) = comma {
+                return Err(JsonParseError::TrailingComma(comma_span).into());
+            }
+            visitor.end_array();
+            Ok(None)
+        }
+        Some(token) => Ok(Some(token)),
+        None => Err(JsonParseError::ExpectedEndOfArray(events.span).into()),
+    }
+}
+
+/// Reads what follows a value inside `context`, giving back the span of the
+/// comma before the next member, or `None` if the container ends there
+fn visit_after_value<V: Visitor>(
+    context: BracketContext,
+    events: &mut Events,
+    visitor: &mut V,
+) -> Result<Option<Span>, JsonError> {
+    match (context, events.next()?) {
+        (_, Some(JsonTokenRef::Comma)) => Ok(Some(events.span)),
+        (BracketContext::Object, Some(JsonTokenRef::CloseCurlyBracket)) => {
+            visitor.end_object();
+            Ok(None)
+        }
+        (BracketContext::Array, Some(JsonTokenRef::CloseSquareBracket)) => {
+            visitor.end_array();
+            Ok(None)
+        }
+        (_, t) => {
+            let t = t.map(JsonTokenRef::into_owned);
+            if let Some(t) = &t {
+                context.check(t, events.span)?;
+            }
+            let err = match context {
+                BracketContext::Object => {
+                    JsonParseError::ExpectedCommaOrEndOfObject(t, events.span)
+                }
+                _ => JsonParseError::ExpectedCommaOrEndOfArray(t, events.span),
+            };
+            Err(err.into())
+        }
+    }
+}
+
+/// Visits the members of the container `root` just opened, and everything
+/// nested in them. Open containers are kept on a heap stack rather than the
+/// call stack, so deep nesting can't overflow it
+fn visit_container<V: Visitor>(
+    root: BracketContext,
+    events: &mut Events,
+    visitor: &mut V,
+) -> Result<(), JsonError> {
+    let mut stack = vec![root];
+    // Comma before the next member of the innermost container
+    let mut comma = None;
+
+    loop {
+        let context = stack[stack.len() - 1];
+
+        // First token of the next member, or `None` if the container ended
+        let token = match context {
+            BracketContext::Object => match visit_key(events, visitor, comma)? {
+                true => Some(events.next()?),
+                false => None,
+            },
+            _ => visit_element(events, visitor, comma)?.map(Some),
+        };
+
+        let mut closed = match token {
+            Some(token) => match visit_value(token, context, events, visitor)? {
+                Some(opened) => {
+                    stack.push(opened);
+                    comma = None;
+                    continue;
+                }
+                None => false,
+            },
+            None => true,
+        };
+
+        // A finished value may be followed by the end of any number of containers
+        loop {
+            if closed {
+                stack.pop();
+            }
+            let Some(&context) = stack.last() else {
+                return Ok(());
+            };
+
+            match visit_after_value(context, events, visitor)? {
+                Some(comma_span) => {
+                    comma = Some(comma_span);
+                    break;
+                }
+                None => closed = true,
+            }
+        }
+    }
+}
+
+/// Lexes and parses `input`, reporting each value to `visitor` as it is found
+/// instead of building a [`JsonValue`](crate::JsonValue) tree. Unescaped strings
+/// are handed over straight from the input, so memory use doesn't grow with
+/// the size of the document
+pub fn parse_events<V: Visitor>(input: &str, visitor: &mut V) -> Result<(), JsonError> {
+    let mut events = Events {
+        lexer: Lexer::new(input, &LexerOptions::default()),
+        span: Span { line: 1, col: 1 },
+    };
+
+    match events.next()? {
+        Some(JsonTokenRef::OpenCurlyBracket) => {
+            visitor.begin_object();
+            visit_container(BracketContext::Object, &mut events, visitor)?;
+        }
+        Some(JsonTokenRef::OpenSquareBracket) => {
+            visitor.begin_array();
+            visit_container(BracketContext::Array, &mut events, visitor)?;
+        }
+        Some(first_token) => {
+            let first_token = first_token.into_owned();
+            BracketContext::Root.check(&first_token, events.span)?;
+            return Err(
+                JsonParseError::ExpectedObjectOrArrayAsRoot(first_token, events.span).into(),
+            );
+        }
+        None => return Err(JsonParseError::NoTokens.into()),
+    }
+
+    if let Some(token) = events.next()? {
+        let token = token.into_owned();
+        BracketContext::Root.check(&token, events.span)?;
+        return Err(JsonParseError::TrailingTokens(token, events.span).into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::JsonError,
+        lexer::{JsonToken, JsonTokenError, LexError, Span},
+        number::JsonNumber,
+        parser::{BracketContext, JsonParseError},
+    };
+
+    use super::{parse_events, Visitor};
+
+    /// Writes every event down as a short string
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl Visitor for Recorder {
+        fn begin_object(&mut self) {
+            self.0.push("{".into());
+        }
+        fn key(&mut self, key: &str) {
+            self.0.push(format!("key {}", key));
+        }
+        fn end_object(&mut self) {
+            self.0.push("}".into());
+        }
+        fn begin_array(&mut self) {
+            self.0.push("[".into());
+        }
+        fn end_array(&mut self) {
+            self.0.push("]".into());
+        }
+        fn string(&mut self, value: &str) {
+            self.0.push(format!("string {}", value));
+        }
+        fn number(&mut self, value: JsonNumber) {
+            self.0.push(format!("number {}", value));
+        }
+        fn bool(&mut self, value: bool) {
+            self.0.push(format!("bool {}", value));
+        }
+        fn null(&mut self) {
+            self.0.push("null".into());
+        }
+    }
+
+    #[test]
+    fn test_event_order() -> Result<(), JsonError> {
+        let mut recorder = Recorder::default();
+
+        parse_events(
+            r#"{"name": "fulano", "roles": [1.5, true, null, {}], "ok": false}"#,
+            &mut recorder,
+        )?;
+
+        let expected = vec![
+            "{",
+            "key name",
+            "string fulano",
+            "key roles",
+            "[",
+            "number 1.5",
+            "bool true",
+            "null",
+            "{",
+            "}",
+            "]",
+            "key ok",
+            "bool false",
+            "}",
+        ];
+
+        assert_eq!(recorder.0, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_count_keys_in_large_object() -> Result<(), JsonError> {
+        #[derive(Default)]
+        struct KeyCounter {
+            depth: usize,
+            keys: usize,
+        }
+
+        impl Visitor for KeyCounter {
+            fn begin_object(&mut self) {
+                self.depth += 1;
+            }
+            fn end_object(&mut self) {
+                self.depth -= 1;
+            }
+            fn key(&mut self, _key: &str) {
+                if self.depth == 1 {
+                    self.keys += 1;
+                }
+            }
+        }
+
+        let members: Vec<String> = (0..10_000)
+            .map(|i| format!(r#""key{}": {{"nested": [{}]}}"#, i, i))
+            .collect();
+        let input = format!("{{{}}}", members.join(","));
+
+        let mut counter = KeyCounter::default();
+        parse_events(&input, &mut counter)?;
+
+        assert_eq!(counter.keys, 10_000);
+        assert_eq!(counter.depth, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_deep_nesting() -> Result<(), JsonError> {
+        const DEPTH: usize = 100_000;
+
+        let input = format!(
+            "{}{}{}",
+            "[{\"a\":".repeat(DEPTH),
+            "null",
+            "}]".repeat(DEPTH)
+        );
+        let mut recorder = Recorder::default();
+        parse_events(&input, &mut recorder)?;
+
+        assert_eq!(recorder.0.len(), DEPTH * 5 + 1);
+        assert_eq!(recorder.0[DEPTH * 3..DEPTH * 3 + 2], ["null", "}"]);
+
+        let unclosed = "[".repeat(DEPTH);
+        assert_eq!(
+            parse_events(&unclosed, &mut recorder),
+            Err(JsonError::Parse(JsonParseError::ExpectedEndOfArray(Span {
+                line: 1,
+                col: DEPTH
+            })))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut recorder = Recorder::default();
+
+        assert_eq!(
+            parse_events(r#"{"a": 1,}"#, &mut recorder),
+            Err(JsonError::Parse(JsonParseError::TrailingComma(Span {
+                line: 1,
+                col: 8
+            })))
+        );
+        assert_eq!(
+            parse_events("[1, x]", &mut recorder),
+            Err(JsonError::Token(LexError {
+                error: JsonTokenError::InvalidToken('x'),
+                offset: 4
+            }))
+        );
+        assert_eq!(
+            parse_events(r#""root""#, &mut recorder),
+            Err(JsonError::Parse(
+                JsonParseError::ExpectedObjectOrArrayAsRoot(
+                    JsonToken::String("root".into()),
+                    Span { line: 1, col: 1 }
+                )
+            ))
+        );
+        assert_eq!(
+            parse_events(r#"{"a":[}"#, &mut recorder),
+            Err(JsonError::Parse(JsonParseError::UnexpectedClosingBracket(
+                '}',
+                BracketContext::Array,
+                Span { line: 1, col: 7 }
+            )))
+        );
+        assert_eq!(
+            parse_events("[1] 2", &mut recorder),
+            Err(JsonError::Parse(JsonParseError::TrailingTokens(
+                JsonToken::Number("2".into()),
+                Span { line: 1, col: 5 }
+            )))
+        );
+    }
+}
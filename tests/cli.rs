@@ -0,0 +1,543 @@
+use std::{
+    env, fs,
+    io::{Read, Write},
+    net::TcpListener,
+    path::PathBuf,
+    process::{Command, Output, Stdio},
+    thread,
+    time::Duration,
+};
+
+fn crusty_json(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_crusty-json"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Writes the content into a file under the system temp dir, unique per test name
+fn temp_file(name: &str, content: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("crusty-json-{}-{}", std::process::id(), name));
+    fs::write(&path, content).unwrap();
+    path
+}
+
+/// Serves a single HTTP response on a local port and returns its URL
+fn serve_once(status: &'static str, body: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+
+        let mut request = [0; 1024];
+        let _ = stream.read(&mut request);
+
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+        stream.write_all(response.as_bytes()).unwrap();
+    });
+
+    url
+}
+
+/// Accepts a single connection and never answers it, holding it open for a while
+fn serve_stalled() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    thread::spawn(move || {
+        let (_stream, _) = listener.accept().unwrap();
+        thread::sleep(Duration::from_secs(10));
+    });
+
+    url
+}
+
+#[test]
+fn test_pretty() {
+    let output = crusty_json(&["--pretty", r#"{"name": "fulano", "tags": [1, true]}"#]);
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\n  \"name\": \"fulano\",\n  \"tags\": [\n    1,\n    true\n  ]\n}\n"
+    );
+}
+
+#[test]
+fn test_compact() {
+    let output = crusty_json(&[
+        "--compact",
+        "{\n  \"name\" : \"fulano\",\n\t\"tags\": [ 1 , true , null ] \n}",
+    ]);
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\"name\":\"fulano\",\"tags\":[1,true,null]}\n"
+    );
+}
+
+#[test]
+fn test_tokens() {
+    let output = crusty_json(&[r#"{"a": [1, null]}"#]);
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\n  \"a\": [\n    1,\n    null\n  ]\n}\n"
+    );
+
+    let output = crusty_json(&["--tokens", "--compact", "[1, null]"]);
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "Tokens: [OpenSquareBracket, Number(\"1\"), Comma, Null, CloseSquareBracket]\n[1,null]\n"
+    );
+}
+
+#[test]
+fn test_escape_non_ascii() {
+    let input = r#"{"mood": "😀", "name": "José"}"#;
+
+    let output = crusty_json(&["--compact", input]);
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\"mood\":\"😀\",\"name\":\"José\"}\n"
+    );
+
+    let output = crusty_json(&["--compact", "--escape-non-ascii", input]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    // The emoji is beyond the BMP, so it takes a surrogate pair
+    assert_eq!(
+        stdout,
+        "{\"mood\":\"\\ud83d\\ude00\",\"name\":\"Jos\\u00e9\"}\n"
+    );
+    assert!(stdout.is_ascii());
+
+    let output = crusty_json(&["--escape-non-ascii", input]);
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\n  \"mood\": \"\\ud83d\\ude00\",\n  \"name\": \"Jos\\u00e9\"\n}\n"
+    );
+}
+
+#[test]
+fn test_validate() {
+    let output = crusty_json(&["--validate", r#"{"name": "fulano"}"#]);
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(output.stderr.is_empty());
+
+    let output = crusty_json(&["--validate", r#"{"name" "fulano"}"#]);
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Expected colon after key"));
+}
+
+#[test]
+fn test_exit_status_inline() {
+    assert!(crusty_json(&["[1, 2]"]).status.success());
+    assert!(!crusty_json(&["[1, 2"]).status.success());
+}
+
+#[test]
+fn test_exit_status_file() {
+    let valid = temp_file("valid.json", r#"{"a": 1}"#);
+    let invalid = temp_file("invalid.json", r#"{"a": }"#);
+
+    assert!(crusty_json(&["-f", valid.to_str().unwrap()])
+        .status
+        .success());
+    assert!(!crusty_json(&["-f", invalid.to_str().unwrap()])
+        .status
+        .success());
+
+    let missing = env::temp_dir().join("crusty-json-missing.json");
+    let output = crusty_json(&["-f", missing.to_str().unwrap()]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .starts_with("Error: "));
+
+    fs::remove_file(valid).unwrap();
+    fs::remove_file(invalid).unwrap();
+}
+
+#[test]
+fn test_exit_status_url() {
+    let url = serve_once("200 OK", r#"{"a": 1}"#);
+    assert!(crusty_json(&["-u", &url]).status.success());
+
+    let url = serve_once("200 OK", r#"{"a" 1}"#);
+    assert!(!crusty_json(&["-u", &url]).status.success());
+}
+
+#[test]
+fn test_multiple_files() {
+    let valid = temp_file("multi-valid.json", r#"{"a": 1}"#);
+    let invalid = temp_file("multi-invalid.json", r#"{"a": }"#);
+
+    let output = crusty_json(&[
+        "--compact",
+        "-f",
+        invalid.to_str().unwrap(),
+        "-f",
+        valid.to_str().unwrap(),
+    ]);
+
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!(
+            "==> {} <==\n==> {} <==\n{{\"a\":1}}\n",
+            invalid.display(),
+            valid.display()
+        )
+    );
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Invalid json value"));
+
+    fs::remove_file(valid).unwrap();
+    fs::remove_file(invalid).unwrap();
+}
+
+#[test]
+fn test_query() {
+    let json = r#"{"user": {"name": "fulano", "roles": ["admin", "dev"]}}"#;
+
+    let output = crusty_json(&["--query", "/user/name", json]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "\"fulano\"\n");
+
+    let output = crusty_json(&["--query", "/user/roles", json]);
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "[\"admin\",\"dev\"]\n"
+    );
+}
+
+#[test]
+fn test_query_unresolved() {
+    let output = crusty_json(&["--query", "/user/age", r#"{"user": {"name": "fulano"}}"#]);
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "Error: No value found at `/user/age`\n"
+    );
+}
+
+#[test]
+fn test_color() {
+    let json = r#"{"name": "fulano", "age": 20, "admin": false}"#;
+
+    let output = crusty_json(&["--pretty", "--color=never", json]);
+    assert!(!output.stdout.contains(&b'\x1b'));
+
+    let output = crusty_json(&["--pretty", "--color=always", json]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains("\x1b[1;34m\"name\"\x1b[0m"));
+    assert!(stdout.contains("\x1b[33m20\x1b[0m"));
+
+    // Output captured by the test isn't a terminal
+    let output = crusty_json(&["--pretty", json]);
+    assert!(!output.stdout.contains(&b'\x1b'));
+}
+
+#[test]
+fn test_sort_keys() {
+    let output = crusty_json(&["--compact", "--sort-keys", r#"{"b":1,"a":{"d":1,"c":2}}"#]);
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\"a\":{\"c\":2,\"d\":1},\"b\":1}\n"
+    );
+}
+
+#[test]
+fn test_ndjson() {
+    let path = temp_file("ndjson.jsonl", "{\"id\": 1}\n\n{\"id\": 2,}\n[3]\n");
+    let output = crusty_json(&["--ndjson", "--compact", "-f", path.to_str().unwrap()]);
+
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\"id\":1}\n[3]\n"
+    );
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .starts_with("Error: line 3: "));
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_url_timeout() {
+    let url = serve_stalled();
+    let output = crusty_json(&["-u", &url, "--timeout", "1"]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .starts_with("Error: "));
+}
+
+#[test]
+fn test_url_max_bytes() {
+    let url = serve_once("200 OK", r#"{"name": "fulano", "tags": [1, 2, 3]}"#);
+    let output = crusty_json(&["-u", &url, "--max-bytes", "16"]);
+
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "Error: Response body is larger than 16 bytes\n"
+    );
+
+    let url = serve_once("200 OK", r#"{"a": 1}"#);
+    let output = crusty_json(&["-u", &url, "--max-bytes", "16", "--compact"]);
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "{\"a\":1}\n");
+}
+
+#[test]
+fn test_url_http_error() {
+    let url = serve_once("500 Internal Server Error", "<h1>Oops</h1>");
+    let output = crusty_json(&["-u", &url]);
+
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!("Error: HTTP 500 Internal Server Error from {}\n", url)
+    );
+}
+
+#[test]
+fn test_output_file() {
+    let input = temp_file("output-in.json", r#"{"a": [1, 2]}"#);
+    let output_path = env::temp_dir().join(format!("crusty-json-{}-out.json", std::process::id()));
+
+    let output = crusty_json(&[
+        "-f",
+        input.to_str().unwrap(),
+        "--pretty",
+        "--output",
+        output_path.to_str().unwrap(),
+    ]);
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        fs::read_to_string(&output_path).unwrap(),
+        "{\n  \"a\": [\n    1,\n    2\n  ]\n}\n"
+    );
+
+    let output = crusty_json(&["--output", output_path.to_str().unwrap(), r#"{"a" 1}"#]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .starts_with("Error: "));
+    assert_eq!(fs::read_to_string(&output_path).unwrap(), "");
+
+    fs::remove_file(input).unwrap();
+    fs::remove_file(output_path).unwrap();
+}
+
+#[test]
+fn test_diff() {
+    let a = temp_file("diff-a.json", r#"{"port": 80, "hosts": ["a", "b"]}"#);
+    let b = temp_file("diff-b.json", r#"{"hosts": ["a", "b"], "port": 8080}"#);
+
+    let output = crusty_json(&["-f", a.to_str().unwrap(), "--diff", b.to_str().unwrap()]);
+
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "/port: changed 80 -> 8080\n"
+    );
+
+    let output = crusty_json(&["-f", a.to_str().unwrap(), "--diff", a.to_str().unwrap()]);
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    fs::remove_file(a).unwrap();
+    fs::remove_file(b).unwrap();
+}
+
+#[test]
+fn test_stats() {
+    let output = crusty_json(&[
+        "--stats",
+        r#"{"name": "fulano", "tags": ["a", "añejo", null], "age": 20, "admin": false, "x": {}}"#,
+    ]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "Nodes: 9\nDepth: 3\nObjects: 2\nArrays: 1\nStrings: 3\nNumbers: 1\nBooleans: 1\nNulls: 1\nLongest string: 6\n"
+    );
+}
+
+#[test]
+fn test_env() {
+    let output = Command::new(env!("CARGO_BIN_EXE_crusty-json"))
+        .args(["--env", "CRUSTY_JSON_TEST_INPUT", "--compact"])
+        .env("CRUSTY_JSON_TEST_INPUT", r#"{ "a": [1, true] }"#)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\"a\":[1,true]}\n"
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_crusty-json"))
+        .args(["--env", "CRUSTY_JSON_TEST_UNSET"])
+        .env_remove("CRUSTY_JSON_TEST_UNSET")
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "Error: $CRUSTY_JSON_TEST_UNSET: environment variable not found\n"
+    );
+}
+
+#[test]
+fn test_stdin_in_two_chunks() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_crusty-json"))
+        .arg("--compact")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(br#"{"a": [1, "#).unwrap();
+    stdin.flush().unwrap();
+    // Nothing is available for a while, which must not be mistaken for no input
+    thread::sleep(Duration::from_millis(300));
+    stdin.write_all(br#"2], "b": null}"#).unwrap();
+    drop(stdin);
+
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\"a\":[1,2],\"b\":null}\n"
+    );
+}
+
+#[test]
+fn test_no_stdin() {
+    // `output` runs the command without any stdin
+    let output = crusty_json(&["--compact", "[1]"]);
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "[1]\n");
+
+    let output = crusty_json(&[]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("expected in-line json, --file, --url, --env or piped stdin"));
+}
+
+#[test]
+fn test_stdin_ignored_with_other_input() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_crusty-json"))
+        .args(["--compact", "[2]"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // The pipe is left open, so reading it would never finish
+    let _stdin = child.stdin.take().unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "[2]\n");
+}
+
+#[test]
+fn test_large_stdin() {
+    let records: Vec<String> = (0..2_000)
+        .map(|i| format!(r#"{{"id": {}, "name": "fulano {}"}}"#, i, i))
+        .collect();
+    let input = format!("[{}]", records.join(",\n"));
+    assert!(input.len() > 64 * 1024);
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_crusty-json"))
+        .arg("--stats")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let mut stdin = child.stdin.take().unwrap();
+    let writer = thread::spawn(move || {
+        for chunk in input.as_bytes().chunks(4096) {
+            stdin.write_all(chunk).unwrap();
+            stdin.flush().unwrap();
+            thread::sleep(Duration::from_millis(1));
+        }
+    });
+
+    let output = child.wait_with_output().unwrap();
+    writer.join().unwrap();
+
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .starts_with("Nodes: 6001\n"));
+}
+
+#[test]
+fn test_unbalanced_brackets() {
+    let output = crusty_json(&["{\"a\": [1, 2}"]);
+
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "Error: Mismatched `}` at line 1, column 12, expected `]` to close the bracket at line 1, column 7\n"
+    );
+}
+
+#[test]
+fn test_deep_nesting() {
+    let path = temp_file(
+        "deep.json",
+        &format!("{}{}", "[".repeat(100_000), "]".repeat(100_000)),
+    );
+    let path = path.to_str().unwrap();
+
+    for args in [vec!["-f", path], vec!["--validate", "-f", path]] {
+        let output = crusty_json(&args);
+
+        // A plain failure rather than a stack overflow
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(
+            String::from_utf8(output.stderr).unwrap(),
+            "Error: Containers nested deeper than 128 levels at line 1, column 129\n"
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use crusty_json::{
+    from_str, lexer, parser, JsonError, JsonObject, JsonParseError, JsonToken, JsonTokenError,
+    JsonValue, LexError, Span,
+};
+
+#[test]
+fn test_from_str() {
+    let json = from_str(r#"{"name": "fulano", "tags": [true, null]}"#).unwrap();
+
+    let mut obj = JsonObject::new();
+    obj.insert("name".into(), JsonValue::String("fulano".into()));
+    obj.insert(
+        "tags".into(),
+        JsonValue::Array(vec![JsonValue::Boolean(true), JsonValue::Null]),
+    );
+
+    assert_eq!(json, JsonValue::Object(obj));
+}
+
+#[test]
+fn test_lexer_and_parser() {
+    let tokens = lexer("[20]").unwrap();
+
+    assert_eq!(
+        tokens,
+        vec![
+            (JsonToken::OpenSquareBracket, Span { line: 1, col: 1 }),
+            (JsonToken::Number("20".into()), Span { line: 1, col: 2 }),
+            (JsonToken::CloseSquareBracket, Span { line: 1, col: 4 }),
+        ]
+    );
+    assert_eq!(
+        parser(&tokens).unwrap(),
+        JsonValue::Array(vec![JsonValue::Number(20.into())])
+    );
+}
+
+#[test]
+fn test_errors() {
+    let err = from_str(r#"{"name" "fulano"}"#).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<JsonParseError>(),
+        Some(&JsonParseError::ExpectedColonAfterKey(
+            Some(JsonToken::String("fulano".into())),
+            Span { line: 1, col: 9 }
+        ))
+    );
+
+    let err = from_str(r#"{"name": x}"#).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<LexError>(),
+        Some(&LexError {
+            error: JsonTokenError::InvalidToken('x'),
+            offset: 9
+        })
+    );
+    assert_eq!(err.to_string(), "Invalid token, got `x` (byte 9)");
+}
+
+#[test]
+fn test_windows_line_endings() {
+    let json = from_str("{\r\n  \"a\": 1\r\n}").unwrap();
+
+    let mut obj = JsonObject::new();
+    obj.insert("a".into(), JsonValue::Number(1.into()));
+
+    assert_eq!(json, JsonValue::Object(obj));
+}
+
+#[test]
+fn test_parse_trait() -> Result<(), JsonError> {
+    let json: JsonValue = r#"{"a": [1, true]}"#.parse()?;
+
+    let mut obj = JsonObject::new();
+    obj.insert(
+        "a".into(),
+        JsonValue::Array(vec![JsonValue::Number(1.into()), JsonValue::Boolean(true)]),
+    );
+    assert_eq!(json, JsonValue::Object(obj));
+
+    assert_eq!(
+        "[1, x]".parse::<JsonValue>(),
+        Err(JsonError::Token(LexError {
+            error: JsonTokenError::InvalidToken('x'),
+            offset: 4
+        }))
+    );
+    assert_eq!(
+        "[1 2]".parse::<JsonValue>(),
+        Err(JsonError::Parse(JsonParseError::ExpectedCommaOrEndOfArray(
+            Some(JsonToken::Number("2".into())),
+            Span { line: 1, col: 4 }
+        )))
+    );
+
+    Ok(())
+}